@@ -0,0 +1,27 @@
+//! Parsing benchmarks: a typical financial request, and a long message whose private
+//! fields make it several kilobytes long.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use iso8583_parser::{parse_iso8583, Iso8583Builder, Mode};
+
+const REQUEST: &str = "00A8600008000002003038058020C1920100000000000000000100275516040011250021000800388362143741176336D28112011861000000000F303131343430323130313132393633302020202020202000321101313131313131313131311002312E312E312E312E31020330022231021532333634000000000000000000335F2A02036482020800950580000000009A032311259C01009F02060000000000011111111111111111";
+
+fn long_message() -> String {
+    let mut builder = Iso8583Builder::new();
+    builder.set_mti("0200").set_field(3, "000000").set_field(11, "000001");
+    for bit in [46, 47, 56, 57, 58, 59, 61, 62, 63, 122] {
+        builder.set_field(bit, &"X".repeat(999));
+    }
+    builder.build().unwrap()
+}
+
+fn parse_benchmarks(c: &mut Criterion) {
+    let mode = Mode::default();
+    c.bench_function("parse request", |b| b.iter(|| parse_iso8583(black_box(REQUEST), true, &mode, None).unwrap()));
+
+    let message = long_message();
+    c.bench_function("parse long message", |b| b.iter(|| parse_iso8583(black_box(&message), false, &mode, None).unwrap()));
+}
+
+criterion_group!(benches, parse_benchmarks);
+criterion_main!(benches);
@@ -1,3 +1,21 @@
+<?php
+// The options and the last message are kept in a cookie and restored when the page is opened
+// without a message, so they do not have to be set again on every visit
+$settings = ["lengthHeader", "parsePrivateTlv", "parsePrivateLtv", "autoPrivate", "maskPan", "showRaw",
+    "plainOutput", "hexView", "numericEncoding", "filter", "fieldNames", "iso_message", "compareMode", "other_message", "inputFormat"];
+$restored = false;
+// Only the formats known to the parser are accepted
+function input_format() {
+    $format = $_GET['inputFormat'] ?? "hex";
+    return in_array($format, ["hex", "base64", "raw"], true) ? $format : "hex";
+}
+if (isset($_GET['iso_message'])) {
+    setcookie("settings", json_encode(array_intersect_key($_GET, array_flip($settings))), time() + 365 * 24 * 3600);
+} elseif (isset($_COOKIE['settings'])) {
+    $_GET = array_intersect_key(json_decode($_COOKIE['settings'], true) ?: [], array_flip($settings));
+    $restored = true;
+}
+?>
 <!DOCTYPE html>
 <html lang="en">
 <head>
@@ -10,9 +28,13 @@
     <h1>ISO8583 Message Parser</h1>
 
     <form method="get">
-        <label for="inlcudeLengthHeader">The message includes length and header</label>
-        <input type="checkbox" name="inlcudeLengthHeader" id="inlcudeLengthHeader"
-        <?php if ( isset($_GET['inlcudeLengthHeader']) && $_GET['inlcudeLengthHeader'] == "on") {echo "checked";}?>>
+        <label for="lengthHeader">The message includes length and header</label>
+        <select name="lengthHeader" id="lengthHeader">
+            <?php foreach (["auto" => "Detect", "yes" => "Yes", "no" => "No"] as $value => $label) {
+                $selected = ($_GET['lengthHeader'] ?? "auto") === $value ? " selected" : "";
+                echo "<option value=\"$value\"$selected>$label</option>";
+            }?>
+        </select>
         <br/>
         <label for="parsePrivateTlv">Parse Private Tlv</label>
         <input type="checkbox" name="parsePrivateTlv" id="parsePrivateTlv"
@@ -22,27 +44,205 @@
         <input type="checkbox" name="parsePrivateLtv" id="parsePrivateLtv"
         <?php if ( isset($_GET['parsePrivateLtv']) && $_GET['parsePrivateLtv'] == "on") {echo "checked";}?>>
         <br/>
-        <label for="Message">Enter the message:</label>
+        <label for="autoPrivate">Auto Detect Private TLV</label>
+        <input type="checkbox" name="autoPrivate" id="autoPrivate"
+        <?php if ( isset($_GET['autoPrivate']) && $_GET['autoPrivate'] == "on") {echo "checked";}?>>
+        <br/>
+        <label for="maskPan">Mask PAN</label>
+        <input type="checkbox" name="maskPan" id="maskPan"
+        <?php if ( isset($_GET['maskPan']) && $_GET['maskPan'] == "on") {echo "checked";}?>>
+        <br/>
+        <label for="showRaw">Show raw hex</label>
+        <input type="checkbox" name="showRaw" id="showRaw"
+        <?php if ( isset($_GET['showRaw']) && $_GET['showRaw'] == "on") {echo "checked";}?>>
+        <br/>
+        <label for="plainOutput">Plain output (no colors)</label>
+        <input type="checkbox" name="plainOutput" id="plainOutput"
+        <?php if ( isset($_GET['plainOutput']) && $_GET['plainOutput'] == "on") {echo "checked";}?>>
+        <br/>
+        <label for="hexView">Show hex view</label>
+        <input type="checkbox" name="hexView" id="hexView"
+        <?php if ( isset($_GET['hexView']) && $_GET['hexView'] == "on") {echo "checked";}?>>
+        <br/>
+        Numeric fields:
+        <input type="radio" name="numericEncoding" id="numericBcd" value="bcd"
+        <?php if (($_GET['numericEncoding'] ?? "bcd") !== "ascii") {echo "checked";}?>>
+        <label for="numericBcd">BCD</label>
+        <input type="radio" name="numericEncoding" id="numericAscii" value="ascii"
+        <?php if (($_GET['numericEncoding'] ?? "bcd") === "ascii") {echo "checked";}?>>
+        <label for="numericAscii">ASCII</label>
+        <br/>
+        <label for="filter">Show only fields matching</label>
+        <input type="text" name="filter" id="filter"
+        value="<?php echo htmlspecialchars($_GET['filter'] ?? '') ?>">
         <br/>
-        <span style="color:Gray">(e.g. '01002000000000000000930000')</span>
+        <label for="fieldNames">Field names, one BIT=NAME per line</label>
         <br/>
-        <span style="color:Gray">(e.g. '0012600008000001002000000000000000930000' 
-        while including header and length)</span>
+        <textarea id="fieldNames" name="fieldNames" rows="3" cols="50"
+        placeholder="63=Loyalty Data"><?php echo htmlspecialchars($_GET['fieldNames'] ?? '') ?></textarea>
         <br/>
-        <textarea required value="<?php echo $_GET['iso_message']?? '' ?>"
+        <label for="example">Try an example:</label>
+        <select id="example" onchange="useExample(this)">
+            <option value="">-</option>
+            <?php
+            // Each example is listed as its name, the message and the options it needs
+            exec("target\\release\\iso8583_parser.exe --list-examples 2>&1", $examples);
+            foreach ($examples as $example) {
+                $parts = explode("\t", $example);
+                if (count($parts) === 3) {
+                    echo '<option value="' . htmlspecialchars($parts[1]) . '" data-options="' . htmlspecialchars($parts[2]) . '">'
+                        . htmlspecialchars($parts[0]) . '</option>';
+                }
+            }
+            ?>
+        </select>
+        <br/>
+        <label for="inputFormat">Message format</label>
+        <select name="inputFormat" id="inputFormat" onchange="validateMessage(document.getElementById('iso_message'), 'messageStatus')">
+            <?php foreach (["hex" => "Hex", "base64" => "Base64", "raw" => "Raw text"] as $value => $label) {
+                $selected = input_format() === $value ? " selected" : "";
+                echo "<option value=\"$value\"$selected>$label</option>";
+            }?>
+        </select>
+        <br/>
+        <label for="Message">Enter the message:</label>
+        <br/>
+        <textarea required value="<?php echo htmlspecialchars($_GET['iso_message'] ?? '') ?>"
         type="text" id="iso_message" name="iso_message"
-        rows="10" cols="50" ><?php if ( isset($_GET['iso_message'])) echo $_GET['iso_message']?></textarea>
+        rows="10" cols="50" oninput="validateMessage(this, 'messageStatus')"><?php echo htmlspecialchars($_GET['iso_message'] ?? '') ?></textarea>
         <br/>
-        <button type="submit">Parse Message</button>
+        <span id="messageStatus"></span>
+        <br/>
+        <label for="compareMode">Compare with a second message</label>
+        <input type="checkbox" name="compareMode" id="compareMode" onchange="showCompareMode()"
+        <?php if ( isset($_GET['compareMode']) && $_GET['compareMode'] == "on") {echo "checked";}?>>
+        <div id="compareInput">
+            <label for="other_message">Enter the second message:</label>
+            <br/>
+            <textarea id="other_message" name="other_message"
+            rows="10" cols="50" oninput="validateMessage(this, 'otherMessageStatus')"><?php echo htmlspecialchars($_GET['other_message'] ?? '') ?></textarea>
+            <br/>
+            <span id="otherMessageStatus"></span>
+        </div>
+        <button type="submit" id="submitButton" title="Ctrl+Enter">Parse Message</button>
     </form>
 
+    <details>
+        <summary>Supported fields</summary>
+        <pre><?php
+        exec("target\\release\\iso8583_parser.exe --list-fields 2>&1", $supported_fields);
+        echo htmlspecialchars(implode("\n", $supported_fields));
+        ?></pre>
+    </details>
+
+    <div id="output">
     <?php
-    if (isset($_GET['iso_message'])) {
+    // Color of the lines of a field, by category: sensitive card data, amounts and the rest
+    function field_color($field_number, $response_color) {
+        if ($field_number === 39) {
+            return $response_color;
+        }
+        if (in_array($field_number, [2, 35, 45, 52])) {
+            return "Crimson";
+        }
+        if (in_array($field_number, [4, 5, 6, 54])) {
+            return "ForestGreen";
+        }
+        return null;
+    }
+
+    // Color of field 39 by the category of its response code printed by the parser
+    function response_code_color($lines) {
+        $colors = ["approved" => "Green", "referral" => "DarkGoldenrod", "declined" => "Red"];
+        foreach ($lines as $line) {
+            if (preg_match('/^Response Category: (\w+)/', $line, $matches)) {
+                return $colors[$matches[1]] ?? null;
+            }
+        }
+        return null;
+    }
+
+    function colorize_output($lines, $plain) {
+        $response_color = response_code_color($lines);
+        $html = "";
+        $color = null;
+        $in_field = false;
+        foreach ($lines as $line) {
+            if (preg_match('/^Field\s+(\d+) /', $line, $matches)) {
+                $color = $plain ? null : field_color((int)$matches[1], $response_color);
+                // Hovering a field highlights it in the hex view
+                $html .= ($in_field ? '</span>' : '') . '<span onmouseover="highlightField(' . $matches[1]
+                    . ')" onmouseout="highlightField(null)">';
+                $in_field = true;
+            } elseif (!preg_match('/^\s/', $line)) {
+                // Message length, header, MTI and bitmap lines
+                $color = $plain ? null : "SteelBlue";
+                $html .= $in_field ? '</span>' : '';
+                $in_field = false;
+            }
+            $escaped = htmlspecialchars($line);
+            $html .= ($color === null ? $escaped : '<span style="color:' . $color . '">' . $escaped . '</span>') . "\n";
+        }
+        return $html . ($in_field ? '</span>' : '');
+    }
+
+    // The fields of two messages parsed with --json side by side, with the values that differ highlighted
+    function compare_table($left, $right) {
+        $fields = [];
+        foreach ([$left, $right] as $side => $result) {
+            foreach ($result['fields'] as $field) {
+                $fields[$field['number']]['name'] = $field['name'];
+                $fields[$field['number']][$side] = $field['value'];
+            }
+        }
+        ksort($fields);
+        $rows = [["MTI", $left['mti'], $right['mti']]];
+        foreach ($fields as $number => $field) {
+            $rows[] = ["Field $number " . $field['name'], $field[0] ?? null, $field[1] ?? null];
+        }
+        $html = '<table border="1" cellpadding="4" style="border-collapse:collapse; font-family:monospace">';
+        $html .= '<tr><th></th><th>Message A</th><th>Message B</th></tr>';
+        foreach ($rows as [$item, $a, $b]) {
+            // Fields present in one message only are grayed, changed values are highlighted
+            $style = $a === $b ? '' : ' style="background-color:' . ($a === null || $b === null ? 'LightGray' : 'Khaki') . '"';
+            $html .= "<tr$style><td>" . htmlspecialchars($item) . '</td><td>' . htmlspecialchars($a ?? '(absent)')
+                . '</td><td>' . htmlspecialchars($b ?? '(absent)') . '</td></tr>';
+        }
+        return $html . '</table>';
+    }
+
+    // The message in hex with the span of each field, as reported by --offsets, tagged by field number
+    function hex_view($message, $lines) {
+        $format = input_format();
+        if ($format === "base64") {
+            $message = bin2hex(base64_decode($message));
+        } elseif ($format === "raw") {
+            $message = bin2hex($message);
+        }
+        $hex = strtoupper(preg_replace('/0x|[^0-9A-Fa-f]/', '', $message));
+        $html = "";
+        $cursor = 0;
+        foreach ($lines as $line) {
+            if (preg_match('/^Field\s+(\d+) .*Offset:\s*(\d+)\.\.\s*(\d+)/', $line, $matches)) {
+                $start = (int)$matches[2];
+                $end = (int)$matches[3];
+                $html .= substr($hex, $cursor, $start - $cursor);
+                $html .= '<span id="hex-' . $matches[1] . '">' . substr($hex, $start, $end - $start) . '</span>';
+                $cursor = $end;
+            }
+        }
+        return $html . substr($hex, $cursor);
+    }
+
+    if (isset($_GET['iso_message']) && !$restored) {
     
         $iso_message = $_GET['iso_message'];
-        $parser_arguments = "-m " . $iso_message;
-        if(isset($_GET['inlcudeLengthHeader'])) {
+        $parser_arguments = "";
+        $length_header = $_GET['lengthHeader'] ?? "auto";
+        if($length_header === "yes") {
             $parser_arguments = "-i " . $parser_arguments;
+        } elseif($length_header === "auto") {
+            $parser_arguments = "--auto-header " . $parser_arguments;
         }
         if(isset($_GET['parsePrivateTlv'])) {
             $parser_arguments = "-t " . $parser_arguments;
@@ -50,20 +250,185 @@
         if(isset($_GET['parsePrivateLtv'])) {
             $parser_arguments = "-l " . $parser_arguments;
         }
+        if(isset($_GET['autoPrivate'])) {
+            $parser_arguments = "-a " . $parser_arguments;
+        }
+        if(isset($_GET['maskPan'])) {
+            $parser_arguments = "-p " . $parser_arguments;
+        }
+        if(isset($_GET['showRaw'])) {
+            $parser_arguments = "-r " . $parser_arguments;
+        }
+        if(isset($_GET['hexView'])) {
+            $parser_arguments = "--offsets " . $parser_arguments;
+        }
+        if(($_GET['numericEncoding'] ?? "bcd") === "ascii") {
+            $parser_arguments = "--ascii " . $parser_arguments;
+        }
+        foreach (preg_split('/\R/', $_GET['fieldNames'] ?? '') as $field_name) {
+            // Lines that are not BIT=NAME are ignored rather than handed to the parser
+            if (preg_match('/^\d{1,3}=.+$/', trim($field_name))) {
+                $parser_arguments = "--field-name " . escapeshellarg(trim($field_name)) . " " . $parser_arguments;
+            }
+        }
+        if(input_format() !== "hex") {
+            $parser_arguments = "--input-format " . input_format() . " " . $parser_arguments;
+        }
+        if(isset($_GET['filter']) && $_GET['filter'] !== "") {
+            $parser_arguments = "--filter " . escapeshellarg($_GET['filter']) . " " . $parser_arguments;
+        }
         $output = [];
 
-        // Use shell_exec to run the Rust command with the iso_message as argument
-        exec("target\\release\\iso8583_parser.exe $parser_arguments 2>&1", $output, $returnCode);
-        if ($returnCode !== 0) {
-            echo '<p>Error parsing message. Return code: ' . $returnCode . '</p>';
-            echo '<p>Error output:  </p>';
-            echo '<font color="red">' . implode("<br/>", $output) . '</font>';
+        if (isset($_GET['compareMode']) && trim($_GET['other_message'] ?? '') !== "") {
+            $results = [];
+            $errors = [];
+            foreach (["A" => $iso_message, "B" => $_GET['other_message']] as $name => $message) {
+                $json = [];
+                exec("target\\release\\iso8583_parser.exe $parser_arguments--json -m " . escapeshellarg($message) . " 2>&1", $json);
+                $result = json_decode(implode("\n", $json), true);
+                if (!is_array($result) || isset($result['error'])) {
+                    $errors[] = "Message $name: " . ($result['error'] ?? implode(" ", $json));
+                } elseif (isset($result[0])) {
+                    // A stream of several messages is parsed into a list of them
+                    $errors[] = "Message $name: compare needs a single message but " . count($result) . " were given";
+                }
+                $results[] = $result;
+            }
+            if ($errors) {
+                echo '<font color="red">' . htmlspecialchars(implode("\n", $errors)) . '</font>';
+            } else {
+                echo '<h2>Compared Messages:</h2>';
+                echo compare_table($results[0], $results[1]);
+            }
         } else {
-            echo '<h2>Parsed Message:</h2>';
-            echo '<pre>' . htmlspecialchars(implode("\n", $output)) . '</pre>';
+            // Use shell_exec to run the Rust command with the iso_message as argument
+            exec("target\\release\\iso8583_parser.exe $parser_arguments--name-width 35 -m " . escapeshellarg($iso_message) . " 2>&1", $output, $returnCode);
+            if ($returnCode !== 0) {
+                echo '<p>Error parsing message. Return code: ' . $returnCode . '</p>';
+                echo '<p>Error output:  </p>';
+                echo '<font color="red">' . implode("<br/>", array_map('htmlspecialchars', $output)) . '</font>';
+            } else {
+                echo '<h2>Parsed Message:</h2>';
+                $warnings_index = array_search("Warnings:", $output);
+                $fields_output = $warnings_index === false ? $output : array_slice($output, 0, $warnings_index);
+                $fields_html = colorize_output($fields_output, isset($_GET['plainOutput']));
+                if (isset($_GET['hexView'])) {
+                    echo '<div style="display:flex; gap:2em">';
+                    echo '<pre style="width:34ch; white-space:pre-wrap; word-break:break-all">' . hex_view($iso_message, $fields_output) . '</pre>';
+                    echo '<pre>' . $fields_html . '</pre>';
+                    echo '</div>';
+                } else {
+                    echo '<pre>' . $fields_html . '</pre>';
+                }
+                if ($warnings_index !== false) {
+                    echo '<h2>Warnings:</h2>';
+                    echo '<pre style="color:DarkOrange">' . htmlspecialchars(implode("\n", array_slice($output, $warnings_index + 1))) . '</pre>';
+                }
+                echo '<button type="button" onclick="copyOutput()">Copy</button> ';
+                echo '<button type="button" onclick="saveOutput()">Save...</button>';
+                echo '<script>const parsedOutput = ' . json_encode(implode("\n", $output), JSON_HEX_TAG) . ';</script>';
+            }
         }
     }
     ?>
+    </div>
+
+    <script>
+        function highlightField(fieldNumber) {
+            document.querySelectorAll('[id^="hex-"]').forEach(function (span) {
+                span.style.backgroundColor = span.id === "hex-" + fieldNumber ? "Yellow" : "";
+            });
+        }
+
+        // The message cleaned up the way normalize_hex of the parser does it, or the reason it can not be
+        function normalizeHex(input) {
+            let hex = "";
+            let tokenStart = true;
+            for (let index = 0; index < input.length; index++) {
+                const c = input[index];
+                if ('" \t\r\n:'.includes(c)) {
+                    tokenStart = true;
+                    continue;
+                }
+                if (c === "0" && tokenStart && "xX".includes(input[index + 1])) {
+                    index++;
+                } else if (/[0-9A-Fa-f]/.test(c)) {
+                    hex += c.toUpperCase();
+                } else {
+                    return { error: "Invalid character '" + c + "' at position " + index };
+                }
+                tokenStart = false;
+            }
+            if (hex.length % 2 !== 0) {
+                return { error: "Odd number of hex digits (" + hex.length + ")" };
+            }
+            return { hex: hex };
+        }
+
+        function validateMessage(textarea, statusId) {
+            const status = document.getElementById(statusId);
+            const normalized = normalizeHex(textarea.value);
+            if (textarea.value.trim() === "" || document.getElementById("inputFormat").value !== "hex") {
+                status.textContent = "";
+            } else if (normalized.error) {
+                status.textContent = normalized.error;
+                status.style.color = "Red";
+            } else {
+                status.textContent = normalized.hex.length / 2 + " bytes";
+                status.style.color = "Gray";
+            }
+        }
+        validateMessage(document.getElementById("iso_message"), "messageStatus");
+        validateMessage(document.getElementById("other_message"), "otherMessageStatus");
+
+        function showCompareMode() {
+            const compare = document.getElementById("compareMode").checked;
+            document.getElementById("compareInput").style.display = compare ? "" : "none";
+            document.getElementById("submitButton").textContent = compare ? "Compare" : "Parse Message";
+        }
+        showCompareMode();
+
+        function useExample(select) {
+            const example = select.options[select.selectedIndex];
+            if (example.value === "") {
+                return;
+            }
+            const options = example.dataset.options.split(" ");
+            document.getElementById("iso_message").value = example.value;
+            document.getElementById("inputFormat").value = "hex";
+            validateMessage(document.getElementById("iso_message"), "messageStatus");
+            document.getElementById("lengthHeader").value = options.includes("-i") ? "yes" : "no";
+            document.getElementById("parsePrivateTlv").checked = options.includes("-t");
+            document.getElementById("parsePrivateLtv").checked = options.includes("-l");
+        }
+
+        // Ctrl+Enter (Cmd+Enter on macOS) in a message parses it, Escape clears the output
+        document.querySelectorAll("#iso_message, #other_message").forEach(function (textarea) {
+            textarea.addEventListener("keydown", function (event) {
+                if (event.key === "Enter" && (event.ctrlKey || event.metaKey)) {
+                    event.preventDefault();
+                    textarea.form.requestSubmit();
+                }
+            });
+        });
+        document.addEventListener("keydown", function (event) {
+            if (event.key === "Escape") {
+                document.getElementById("output").innerHTML = "";
+            }
+        });
+
+        function copyOutput() {
+            navigator.clipboard.writeText(parsedOutput);
+        }
+
+        function saveOutput() {
+            const link = document.createElement("a");
+            link.href = URL.createObjectURL(new Blob([parsedOutput], { type: "text/plain" }));
+            link.download = "parsed_message.txt";
+            link.click();
+            URL.revokeObjectURL(link.href);
+        }
+    </script>
 
 </body>
 </html>
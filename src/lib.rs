@@ -5,7 +5,7 @@
 //! ## Examples
 //!
 //! ```
-//! use iso8583_parser::{StringManipulation, Mode};
+//! use iso8583_parser::{StringManipulation, Mode, FieldSpec, LengthType, Encoding};
 //!
 //! let mut s = String::from("48656C6C6F2C576F726C64"); // "Hello, World" in hex
 //!
@@ -14,16 +14,20 @@
 //! assert_eq!(ascii_result.unwrap(), "Hello,World");
 //! 
 //! // Get a slice of the string until a specified length
-//! let slice = s.get_slice_until(5);
+//! let slice = s.get_slice_until(5).unwrap();
 //! assert_eq!(slice, "48656");
 //! 
 //! // Get another slice of the string until a specified length
-//! let slice = s.get_slice_until(5);
+//! let slice = s.get_slice_until(5).unwrap();
 //! assert_eq!(slice, "C6C6F");
+//!
+//! // Asking for more than what remains is an error
+//! assert!(s.get_slice_until(50).is_err());
 //! 
-//!let mode_instance = Mode { enabled_private_tlv: false, enabled_private_ltv: false };
+//!let mode_instance = Mode::default();
 //! // Process a field based on field number, length, and name
-//! s.process_field(1, 12, "test", &mode_instance);
+//! let spec = FieldSpec::new("test", LengthType::Fixed, 6, Encoding::Binary);
+//! s.process_field(1, 12, &spec, &mode_instance).unwrap();
 //!
 //! use iso8583_parser::positions_of_set_bits;
 //!
@@ -38,55 +42,2903 @@
 //! for ltv in ltvs {
 //!     println!("{}", ltv);
 //! }
+//!
+//! use iso8583_parser::{parse_iso8583, FieldCatalog};
+//!
+//! // Parse a whole message, overriding the definition of field 62
+//! let mut catalog = FieldCatalog::default();
+//! catalog.insert(62, FieldSpec::new("Private Use", LengthType::LLVar, 99, Encoding::Binary));
+//! let result = parse_iso8583("01002000000000000000930000", false, &mode_instance, Some(&catalog)).unwrap();
+//! assert_eq!(result.mti, "0100");
+//! assert_eq!(result.bitmap, vec![3]);
 //! ```
 
-use emv_tlv_parser::parse_tlv;
-use std::error;
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::error;
+use core::fmt;
+use core::num::ParseIntError;
+use des::cipher::generic_array::GenericArray;
+use des::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use des::Des;
+#[cfg(feature = "std")]
+use emv_tlv_parser::parse_tlv_vec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+pub mod server;
+
+/// Errors that can happen while parsing or building an ISO8583 message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Iso8583Error {
+    /// A value is not a valid hex string.
+    InvalidHex(hex::FromHexError),
+    /// A number, such as a length prefix, could not be parsed.
+    InvalidNumber(ParseIntError),
+    /// The length of the message does not match its length header.
+    LengthMismatch { expected: usize, actual: usize },
+    /// The length header of the message is not a valid length.
+    InvalidLengthHeader(String),
+    /// The header is longer than the message following the length header.
+    HeaderTooLong { header_len: usize, available: usize },
+    /// The STX/ETX framing around the message is incomplete.
+    InvalidFraming(String),
+    /// The message, in bytes, is too long for a 2-byte length header.
+    MessageTooLong(usize),
+    /// The field is not defined in the field catalog.
+    UnimplementedField(u32),
+    /// A field needs more characters than what remains in the message.
+    TruncatedField { bit: u32, needed: usize, available: usize },
+    /// The length prefix of a variable length field exceeds the maximum length of the field.
+    FieldTooLong { bit: u32, length: u32, max_length: u32 },
+    /// The message type indicator is malformed.
+    InvalidMti(String),
+    /// The value of a field is malformed.
+    InvalidField { bit: u32, reason: String },
+    /// A private LTV element is malformed.
+    InvalidLtv(String),
+    /// A private TLV element is malformed.
+    InvalidTlv(String),
+    /// The parse result could not be serialized.
+    Serialization(String),
+    /// The message is not valid base64.
+    InvalidBase64(String),
+}
+
+impl fmt::Display for Iso8583Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Iso8583Error::InvalidHex(e) => write!(f, "Invalid hex: {}", e),
+            Iso8583Error::InvalidNumber(e) => write!(f, "{}", e),
+            Iso8583Error::LengthMismatch { expected, actual } => {
+                write!(f, "Incorrect message len. The expected length is {} but The actual is {}", expected, actual)
+            }
+            Iso8583Error::InvalidLengthHeader(header) => write!(f, "Invalid length header: {:?}", header),
+            Iso8583Error::HeaderTooLong { header_len, available } => {
+                write!(f, "The header needs {} characters but the message is only {} characters long", header_len, available)
+            }
+            Iso8583Error::InvalidFraming(reason) => write!(f, "Invalid framing: {}", reason),
+            Iso8583Error::MessageTooLong(length) => {
+                write!(f, "The message is {} bytes long but a length header holds at most {}", length, u16::MAX)
+            }
+            Iso8583Error::UnimplementedField(bit) => write!(f, "Field {} is not implemented", bit),
+            Iso8583Error::TruncatedField { bit, needed, available } => {
+                write!(f, "Field {} needs {} characters but only {} are available", bit, needed, available)
+            }
+            Iso8583Error::FieldTooLong { bit, length, max_length } => {
+                write!(f, "Field {} has a length of {} but its maximum length is {}", bit, length, max_length)
+            }
+            Iso8583Error::InvalidMti(mti) => write!(f, "Invalid MTI: {:?}", mti),
+            Iso8583Error::InvalidField { bit, reason } => write!(f, "Invalid field {}: {}", bit, reason),
+            Iso8583Error::InvalidLtv(reason) => write!(f, "Invalid LTV: {}", reason),
+            Iso8583Error::InvalidTlv(reason) => write!(f, "Invalid private TLV: {}", reason),
+            Iso8583Error::Serialization(reason) => write!(f, "Serialization failed: {}", reason),
+            Iso8583Error::InvalidBase64(reason) => write!(f, "Invalid base64: {}", reason),
+        }
+    }
+}
+
+impl Iso8583Error {
+    /// Attribute a `TruncatedField` or `FieldTooLong` error to the given field.
+    fn in_field(self, bit: u32) -> Self {
+        match self {
+            Iso8583Error::TruncatedField { needed, available, .. } => Iso8583Error::TruncatedField { bit, needed, available },
+            Iso8583Error::FieldTooLong { length, max_length, .. } => Iso8583Error::FieldTooLong { bit, length, max_length },
+            Iso8583Error::InvalidField { reason, .. } => Iso8583Error::InvalidField { bit, reason },
+            e => e,
+        }
+    }
+}
+
+impl error::Error for Iso8583Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Iso8583Error::InvalidHex(e) => Some(e),
+            Iso8583Error::InvalidNumber(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<hex::FromHexError> for Iso8583Error {
+    fn from(e: hex::FromHexError) -> Self {
+        Iso8583Error::InvalidHex(e)
+    }
+}
+
+impl From<ParseIntError> for Iso8583Error {
+    fn from(e: ParseIntError) -> Self {
+        Iso8583Error::InvalidNumber(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct  LTV {
+    pub length: usize,
+    pub tag: u8,
+    pub value: String,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct  PrivateTlv {
+    pub tag: String,
+    pub length: usize,
+    pub value: String,
+}
+
+/// How the length of a private TLV element is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlvLengthEncoding {
+    /// ASCII characters of hex digits, e.g. `3030` for 0x00.
+    AsciiHex,
+    /// ASCII decimal digits, e.g. `3132` for 12.
+    AsciiDecimal,
+    /// A big endian binary number.
+    Binary,
+}
+
+/// The layout of the private TLV elements of fields 48 and 121, which differs between hosts.
+///
+/// Tags and values are decoded following their `Encoding`; with `Bcd` or `Binary` they are
+/// kept in hex. Lengths count the bytes of the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrivateTlvFormat {
+    /// Number of bytes of a tag.
+    pub tag_bytes: usize,
+    pub tag_encoding: Encoding,
+    /// Number of bytes of a length.
+    pub length_bytes: usize,
+    pub length_encoding: TlvLengthEncoding,
+    pub value_encoding: Encoding,
+}
+
+impl PrivateTlvFormat {
+    /// A 2-character ASCII tag, a length of 2 ASCII hex digits and an ASCII value, also used
+    /// by `PrivateTlvFormat::default()`.
+    pub fn ascii() -> Self {
+        PrivateTlvFormat {
+            tag_bytes: 2,
+            tag_encoding: Encoding::Ascii,
+            length_bytes: 2,
+            length_encoding: TlvLengthEncoding::AsciiHex,
+            value_encoding: Encoding::Ascii,
+        }
+    }
+
+    /// A 1-byte binary tag, a 1-byte binary length and a value kept in hex.
+    pub fn binary() -> Self {
+        PrivateTlvFormat {
+            tag_bytes: 1,
+            tag_encoding: Encoding::Binary,
+            length_bytes: 1,
+            length_encoding: TlvLengthEncoding::Binary,
+            value_encoding: Encoding::Binary,
+        }
+    }
+
+    /// Decode a tag or a value given in hex.
+    fn decode(encoding: Encoding, mut hex: String) -> Result<String, Iso8583Error> {
+        match encoding {
+            Encoding::Ascii => Ok(hex.hex_to_ascii()?),
+            Encoding::Ebcdic => Ok(hex.hex_to_ebcdic()?),
+            Encoding::Bcd | Encoding::Binary => Ok(hex),
+        }
+    }
+
+    /// Decode a length given in hex.
+    fn decode_length(&self, mut hex: String) -> Result<usize, Iso8583Error> {
+        match self.length_encoding {
+            TlvLengthEncoding::AsciiHex => Ok(usize::from_str_radix(&hex.hex_to_ascii()?, 16)?),
+            TlvLengthEncoding::AsciiDecimal => Ok(hex.hex_to_ascii()?.parse::<usize>()?),
+            TlvLengthEncoding::Binary => Ok(usize::from_str_radix(&hex, 16)?),
+        }
+    }
+}
+
+impl Default for PrivateTlvFormat {
+    fn default() -> Self {
+        PrivateTlvFormat::ascii()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mode {
+    pub enabled_private_tlv: bool,
+    pub enabled_private_ltv: bool,
+    /// Mask the PAN of fields 2, 35 and 45, keeping only its first 6 and last 4 digits.
+    pub mask_pan: bool,
+    /// How the length prefixes of variable length fields are encoded.
+    pub length_encoding: LengthEncoding,
+    /// Instead of failing on a field missing from the catalog, report the rest of the message
+    /// as an unimplemented field and leave it unparsed. A malformed MTI is also accepted.
+    pub lenient: bool,
+    /// Try private TLV parsing of fields 48 and 121 when neither private format is enabled,
+    /// leaving the raw value alone if it is not valid private TLV.
+    pub auto_detect_private: bool,
+    /// How the values of numeric fields are encoded.
+    pub numeric_encoding: NumericEncoding,
+    /// How the message length header is encoded, when the message includes one.
+    pub length_header: LengthHeaderKind,
+    /// Number of hex characters of the header following the length header.
+    pub header_len: usize,
+    /// How the length declared by the length header is checked.
+    pub length_check: LengthCheck,
+    /// The message is followed by an LRC byte, the XOR of all the bytes before it, which is
+    /// checked and reported in `ParserResult::trailer_ok`. Length headers do not count it.
+    pub lrc: bool,
+    /// Remove the STX (0x02) and ETX (0x03) bytes the message may be wrapped in, and the LRC
+    /// that may follow the ETX. With `lrc` the LRC is required and covers the bytes after
+    /// the STX up to the ETX.
+    pub strip_framing: bool,
+    /// What to do when the EMV data of field 55 can not be decoded.
+    pub tlv_error_policy: TlvErrorPolicy,
+    /// The layout of the private TLV elements of fields 48 and 121.
+    pub private_tlv_format: PrivateTlvFormat,
+    /// The case of the hex digits of the values of BCD and binary fields, of the raw form of
+    /// text fields and of the hex shown in the details of fields, whatever the case of the
+    /// message.
+    pub hex_case: HexCase,
+    /// Names replacing the catalog names of some fields, e.g. for private fields used with
+    /// their own meaning. The length handling of the fields is unchanged.
+    pub field_names: BTreeMap<u32, String>,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode {
+            enabled_private_tlv: false,
+            enabled_private_ltv: false,
+            mask_pan: false,
+            length_encoding: LengthEncoding::default(),
+            lenient: false,
+            auto_detect_private: false,
+            numeric_encoding: NumericEncoding::default(),
+            length_header: LengthHeaderKind::default(),
+            header_len: 10,
+            length_check: LengthCheck::default(),
+            lrc: false,
+            strip_framing: false,
+            tlv_error_policy: TlvErrorPolicy::default(),
+            private_tlv_format: PrivateTlvFormat::default(),
+            hex_case: HexCase::default(),
+            field_names: BTreeMap::new(),
+        }
+    }
+}
+
+/// Options controlling how parse results are formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Width of the field name column.
+    pub name_width: usize,
+    /// Character separating the columns of a field.
+    pub separator: char,
+    /// Show the raw hex next to the decoded value of text fields.
+    pub show_raw: bool,
+    /// Only show the fields whose number or name contains this text, ignoring case.
+    pub filter: Option<String>,
+    /// Show where each field starts and ends in the message.
+    pub show_offsets: bool,
+    /// Show the characters of field values that are not printable ASCII as `.`, so they can
+    /// not corrupt the terminal. The parsed values keep the original characters.
+    pub printable: bool,
+    /// How the bitmap is shown.
+    pub bitmap_format: BitmapFormat,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            name_width: 25,
+            separator: '|',
+            show_raw: false,
+            filter: None,
+            show_offsets: false,
+            printable: true,
+            bitmap_format: BitmapFormat::Positions,
+        }
+    }
+}
+
+/// How the bitmap of a message is shown in its report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitmapFormat {
+    /// The positions of the fields present, e.g. `[3, 11]`.
+    #[default]
+    Positions,
+    /// The bitmaps in hex, e.g. `2020000000000000`.
+    Hex,
+    /// The bitmaps in hex followed by the positions.
+    Both,
+}
+
+/// How the length of a field is determined on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    /// Fixed length field; the length is `FieldSpec::max_length`.
+    Fixed,
+    /// Variable length field with a 2-digit length prefix.
+    LLVar,
+    /// Variable length field with a 4-digit length prefix.
+    LLLVar,
+}
+
+/// How the length prefix of a variable length field is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthEncoding {
+    /// Packed BCD digits: 1 byte for LLVAR and 2 bytes for LLLVAR.
+    #[default]
+    Bcd,
+    /// ASCII digits: 2 characters for LLVAR and 3 characters for LLLVAR.
+    Ascii,
+}
+
+impl LengthEncoding {
+    /// Read a length prefix of the given number of decimal digits from the front of the message.
+    ///
+    /// A BCD digit is one nibble, so the 2 digits of an LLVAR prefix take a single byte (`16`
+    /// is a length of 16, not 0x16) and the 3 digits of an LLLVAR prefix are padded to 2
+    /// bytes. An ASCII digit is a whole byte (`3136` is a length of 16).
+    fn read_prefix(self, cursor: &mut Cursor, digits: usize) -> Result<u32, Iso8583Error> {
+        let prefix = match self {
+            LengthEncoding::Bcd => cursor.take(digits.next_multiple_of(2))?.to_string(),
+            LengthEncoding::Ascii => cursor.take(digits * 2)?.to_string().hex_to_ascii()?,
+        };
+        if !prefix.chars().all(|c| c.is_ascii_digit()) {
+            let reason = match self {
+                LengthEncoding::Bcd => format!("length prefix {} is not made of BCD digits", prefix),
+                LengthEncoding::Ascii => format!("length prefix {:?} is not made of ASCII digits", prefix),
+            };
+            return Err(Iso8583Error::InvalidField { bit: 0, reason });
+        }
+        Ok(prefix.parse::<u32>()?)
+    }
+}
+
+/// How the length declared by the message length header is checked against the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthCheck {
+    /// The message must be exactly as long as declared.
+    #[default]
+    Exact,
+    /// The message may be longer than declared, e.g. when it is followed by an LRC or
+    /// padding. Only the declared length is parsed and the rest is left unparsed.
+    AtLeast,
+    /// The declared length is not checked and the whole message is parsed.
+    Ignore,
+}
+
+/// The case of the hex digits of field values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+impl HexCase {
+    /// The hex string with its digits in this case.
+    fn apply(self, hex: &str) -> String {
+        match self {
+            HexCase::Upper => hex.to_uppercase(),
+            HexCase::Lower => hex.to_lowercase(),
+        }
+    }
+}
+
+/// What to do with EMV data of field 55 that can not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TlvErrorPolicy {
+    /// Report the error in the details of the field and go on.
+    #[default]
+    Ignore,
+    /// Report the error and show the undecoded hex, e.g. for an external EMV decoder.
+    Raw,
+    /// Fail the whole parse.
+    Fail,
+}
+
+/// How the message length indicator (MLI) in front of a message is encoded. Either way it
+/// holds the number of bytes following it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthHeaderKind {
+    /// A 2-byte big endian binary number, e.g. `0022`.
+    #[default]
+    BinaryHex,
+    /// 4 ASCII decimal digits, e.g. `30303334` for "0034".
+    AsciiDecimal,
+}
+
+impl LengthHeaderKind {
+    /// Number of bytes taken by the length header.
+    fn width(self) -> usize {
+        match self {
+            LengthHeaderKind::BinaryHex => 2,
+            LengthHeaderKind::AsciiDecimal => 4,
+        }
+    }
+
+    /// Read the length declared by the header at the front of `data`.
+    fn read(self, data: &[u8]) -> Result<usize, Iso8583Error> {
+        let header = data.get(..self.width())
+            .ok_or_else(|| Iso8583Error::InvalidLengthHeader(hex::encode_upper(data)))?;
+        match self {
+            LengthHeaderKind::BinaryHex => Ok(u16::from_be_bytes([header[0], header[1]]) as usize),
+            LengthHeaderKind::AsciiDecimal => {
+                if !header.iter().all(u8::is_ascii_digit) {
+                    return Err(Iso8583Error::InvalidLengthHeader(hex::encode_upper(header)));
+                }
+                Ok(header.iter().fold(0, |length, &digit| length * 10 + (digit - b'0') as usize))
+            }
+        }
+    }
+}
+
+/// How the values of the numeric fields, the BCD fields of the catalog, are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumericEncoding {
+    /// Packed BCD, one digit per hex character, as defined by the catalog.
+    #[default]
+    Bcd,
+    /// ASCII digits, two hex characters per digit.
+    Ascii,
+}
+
+/// How the value of a field is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Numeric digits, one per hex character. Lengths are counted in digits.
+    Bcd,
+    /// ASCII characters, two hex characters per byte. Lengths are counted in bytes.
+    Ascii,
+    /// EBCDIC (code page 037) characters, two hex characters per byte. Lengths are counted in bytes.
+    Ebcdic,
+    /// Raw bytes, two hex characters per byte. Lengths are counted in bytes.
+    Binary,
+}
+
+/// EBCDIC code page 037 to Unicode translation table.
+const CP037: [char; 256] = [
+    '\u{00}', '\u{01}', '\u{02}', '\u{03}', '\u{9C}', '\u{09}', '\u{86}', '\u{7F}',
+    '\u{97}', '\u{8D}', '\u{8E}', '\u{0B}', '\u{0C}', '\u{0D}', '\u{0E}', '\u{0F}',
+    '\u{10}', '\u{11}', '\u{12}', '\u{13}', '\u{9D}', '\u{85}', '\u{08}', '\u{87}',
+    '\u{18}', '\u{19}', '\u{92}', '\u{8F}', '\u{1C}', '\u{1D}', '\u{1E}', '\u{1F}',
+    '\u{80}', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '\u{0A}', '\u{17}', '\u{1B}',
+    '\u{88}', '\u{89}', '\u{8A}', '\u{8B}', '\u{8C}', '\u{05}', '\u{06}', '\u{07}',
+    '\u{90}', '\u{91}', '\u{16}', '\u{93}', '\u{94}', '\u{95}', '\u{96}', '\u{04}',
+    '\u{98}', '\u{99}', '\u{9A}', '\u{9B}', '\u{14}', '\u{15}', '\u{9E}', '\u{1A}',
+    ' ', '\u{A0}', '\u{E2}', '\u{E4}', '\u{E0}', '\u{E1}', '\u{E3}', '\u{E5}',
+    '\u{E7}', '\u{F1}', '\u{A2}', '.', '<', '(', '+', '|',
+    '&', '\u{E9}', '\u{EA}', '\u{EB}', '\u{E8}', '\u{ED}', '\u{EE}', '\u{EF}',
+    '\u{EC}', '\u{DF}', '!', '$', '*', ')', ';', '\u{AC}',
+    '-', '/', '\u{C2}', '\u{C4}', '\u{C0}', '\u{C1}', '\u{C3}', '\u{C5}',
+    '\u{C7}', '\u{D1}', '\u{A6}', ',', '%', '_', '>', '?',
+    '\u{F8}', '\u{C9}', '\u{CA}', '\u{CB}', '\u{C8}', '\u{CD}', '\u{CE}', '\u{CF}',
+    '\u{CC}', '`', ':', '#', '@', '\'', '=', '"',
+    '\u{D8}', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
+    'h', 'i', '\u{AB}', '\u{BB}', '\u{F0}', '\u{FD}', '\u{FE}', '\u{B1}',
+    '\u{B0}', 'j', 'k', 'l', 'm', 'n', 'o', 'p',
+    'q', 'r', '\u{AA}', '\u{BA}', '\u{E6}', '\u{B8}', '\u{C6}', '\u{A4}',
+    '\u{B5}', '~', 's', 't', 'u', 'v', 'w', 'x',
+    'y', 'z', '\u{A1}', '\u{BF}', '\u{D0}', '\u{DD}', '\u{DE}', '\u{AE}',
+    '^', '\u{A3}', '\u{A5}', '\u{B7}', '\u{A9}', '\u{A7}', '\u{B6}', '\u{BC}',
+    '\u{BD}', '\u{BE}', '[', ']', '\u{AF}', '\u{A8}', '\u{B4}', '\u{D7}',
+    '{', 'A', 'B', 'C', 'D', 'E', 'F', 'G',
+    'H', 'I', '\u{AD}', '\u{F4}', '\u{F6}', '\u{F2}', '\u{F3}', '\u{F5}',
+    '}', 'J', 'K', 'L', 'M', 'N', 'O', 'P',
+    'Q', 'R', '\u{B9}', '\u{FB}', '\u{FC}', '\u{F9}', '\u{FA}', '\u{FF}',
+    '\\', '\u{F7}', 'S', 'T', 'U', 'V', 'W', 'X',
+    'Y', 'Z', '\u{B2}', '\u{D4}', '\u{D6}', '\u{D2}', '\u{D3}', '\u{D5}',
+    '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', '\u{B3}', '\u{DB}', '\u{DC}', '\u{D9}', '\u{DA}', '\u{9F}',
+];
+
+/// Definition of a single ISO8583 field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: String,
+    pub length_type: LengthType,
+    pub max_length: u32,
+    pub encoding: Encoding,
+}
+
+impl FieldSpec {
+    pub fn new(name: &str, length_type: LengthType, max_length: u32, encoding: Encoding) -> Self {
+        FieldSpec { name: name.to_string(), length_type, max_length, encoding }
+    }
+
+    /// Length of the field in hex characters for a given length in field units.
+    fn hex_length(&self, length: u32) -> u32 {
+        match self.encoding {
+            Encoding::Bcd => length,
+            Encoding::Ascii | Encoding::Ebcdic | Encoding::Binary => length * 2,
+        }
+    }
+
+    /// Number of hex characters a field of the given length in hex characters takes in the
+    /// message. Packed BCD values with an odd number of digits are padded to a whole byte.
+    fn wire_length(&self, hex_length: u32) -> u32 {
+        match self.encoding {
+            Encoding::Bcd => hex_length.next_multiple_of(2),
+            Encoding::Ascii | Encoding::Ebcdic | Encoding::Binary => hex_length,
+        }
+    }
+
+    /// Read the length of the field from the front of the message, consuming the
+    /// length prefix of variable length fields. Returns the length in hex characters.
+    ///
+    /// The width of the prefix follows the length type and `encoding`: 2 digits for LLVAR and
+    /// 3 digits, padded to 4 in BCD, for LLLVAR. A length running past the end of the message
+    /// is reported as a truncated field rather than letting the following fields slide out of
+    /// alignment, and a length above `max_length` as a field too long.
+    fn read_length(&self, cursor: &mut Cursor, encoding: LengthEncoding) -> Result<u32, Iso8583Error> {
+        let length = match self.length_type {
+            LengthType::Fixed => self.max_length,
+            LengthType::LLVar => encoding.read_prefix(cursor, 2)?,
+            LengthType::LLLVar => encoding.read_prefix(cursor, 3)?,
+        };
+        if length > self.max_length {
+            return Err(Iso8583Error::FieldTooLong { bit: 0, length, max_length: self.max_length });
+        }
+        let length = self.hex_length(length);
+        if self.wire_length(length) as usize > cursor.remaining() {
+            return Err(Iso8583Error::TruncatedField { bit: 0, needed: self.wire_length(length) as usize, available: cursor.remaining() });
+        }
+        Ok(length)
+    }
+
+    /// Encode a field value the way `read_length` and `process_field` expect to find it,
+    /// including the length prefix of variable length fields.
+    fn encode(&self, bit: u32, value: &str) -> Result<String, Iso8583Error> {
+        let data = match self.encoding {
+            Encoding::Ascii => hex::encode_upper(value),
+            Encoding::Ebcdic => value
+                .chars()
+                .map(|c| CP037.iter().position(|&e| e == c).map(|byte| format!("{:02X}", byte)))
+                .collect::<Option<String>>()
+                .ok_or_else(|| Iso8583Error::InvalidField { bit, reason: "value can not be encoded in EBCDIC".to_string() })?,
+            Encoding::Bcd | Encoding::Binary => value.to_string(),
+        };
+        let length = match self.encoding {
+            Encoding::Bcd => data.len(),
+            Encoding::Ascii | Encoding::Ebcdic | Encoding::Binary => {
+                if data.len() % 2 != 0 {
+                    return Err(Iso8583Error::InvalidField { bit, reason: "value must contain a whole number of bytes".to_string() });
+                }
+                data.len() / 2
+            }
+        } as u32;
+        // Odd numbers of BCD digits are padded to a whole byte: fixed numeric fields are right
+        // justified with a leading 0 and variable ones left justified with a trailing F
+        let data = match (self.encoding, self.length_type) {
+            (Encoding::Bcd, LengthType::Fixed) if length % 2 == 1 => format!("0{}", data),
+            (Encoding::Bcd, _) if length % 2 == 1 => format!("{}F", data),
+            _ => data,
+        };
+        match self.length_type {
+            LengthType::Fixed if length != self.max_length => {
+                Err(Iso8583Error::InvalidField { bit, reason: format!("length must be {} but it is {}", self.max_length, length) })
+            }
+            _ if length > self.max_length => {
+                Err(Iso8583Error::InvalidField { bit, reason: format!("length can be at most {} but it is {}", self.max_length, length) })
+            }
+            LengthType::Fixed => Ok(data),
+            LengthType::LLVar => Ok(format!("{:02}{}", length, data)),
+            LengthType::LLLVar => Ok(format!("{:04}{}", length, data)),
+        }
+    }
+}
+
+/// Field definitions keyed by bit number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldCatalog {
+    fields: BTreeMap<u32, FieldSpec>,
+}
+
+impl FieldCatalog {
+    /// Create a catalog without any field.
+    pub fn empty() -> Self {
+        FieldCatalog { fields: BTreeMap::new() }
+    }
+
+    /// Get the definition of a field.
+    pub fn get(&self, bit: u32) -> Option<&FieldSpec> {
+        self.fields.get(&bit)
+    }
+
+    /// Add or override the definition of a field, returning the previous one.
+    pub fn insert(&mut self, bit: u32, spec: FieldSpec) -> Option<FieldSpec> {
+        self.fields.insert(bit, spec)
+    }
+
+    /// Remove the definition of a field.
+    pub fn remove(&mut self, bit: u32) -> Option<FieldSpec> {
+        self.fields.remove(&bit)
+    }
+
+    /// The bit numbers of the defined fields, in ascending order.
+    pub fn bits(&self) -> Vec<u32> {
+        self.fields.keys().copied().collect()
+    }
+
+    /// Iterate over the defined fields in ascending bit order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &FieldSpec)> {
+        self.fields.iter().map(|(&bit, spec)| (bit, spec))
+    }
+
+    /// The bit numbers of the numeric fields, the ones affected by `Mode::numeric_encoding`.
+    pub fn numeric_fields(&self) -> Vec<u32> {
+        self.iter().filter(|(_, spec)| spec.encoding == Encoding::Bcd).map(|(bit, _)| bit).collect()
+    }
+}
+
+/// The bit numbers of the fields the default catalog can parse, in ascending order.
+pub fn implemented_fields() -> Vec<u32> {
+    FieldCatalog::default().bits()
+}
+
+/// A sample message with the options it is meant to be parsed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExampleMessage {
+    pub name: &'static str,
+    pub message: &'static str,
+    /// Whether the message starts with a length header and a header.
+    pub including_header_length: bool,
+    pub private_tlv: bool,
+    pub private_ltv: bool,
+}
+
+impl ExampleMessage {
+    /// The parse options of the example.
+    pub fn mode(&self) -> Mode {
+        Mode { enabled_private_tlv: self.private_tlv, enabled_private_ltv: self.private_ltv, ..Default::default() }
+    }
+}
+
+/// Sample messages of the formats the parser handles, offered by the web page to try them out.
+pub const EXAMPLE_MESSAGES: &[ExampleMessage] = &[
+    ExampleMessage {
+        name: "Authorization request",
+        message: "01002000000000000000930000",
+        including_header_length: false,
+        private_tlv: false,
+        private_ltv: false,
+    },
+    ExampleMessage {
+        name: "Length and header",
+        message: "0012600008000001002000000000000000930000",
+        including_header_length: true,
+        private_tlv: false,
+        private_ltv: false,
+    },
+    ExampleMessage {
+        name: "Secondary bitmap",
+        message: "08008220000000000000040000000000000010141530120000010301",
+        including_header_length: false,
+        private_tlv: false,
+        private_ltv: false,
+    },
+    ExampleMessage {
+        name: "Private TLV",
+        message: "01000000000000010000000730313033414243",
+        including_header_length: false,
+        private_tlv: true,
+        private_ltv: false,
+    },
+    ExampleMessage {
+        name: "Private LTV",
+        message: "0100202000000001000000000000004500070201AB03024142",
+        including_header_length: false,
+        private_tlv: false,
+        private_ltv: true,
+    },
+];
+
+impl Default for FieldCatalog {
+    fn default() -> Self {
+        FieldCatalog::iso1987()
+    }
+}
+
+impl FieldCatalog {
+    /// Field definitions of ISO 8583:1987, also used by `FieldCatalog::default()`.
+    pub fn iso1987() -> Self {
+        use Encoding::*;
+        use LengthType::*;
+        let table = [
+            (2, "PAN", LLVar, 19, Bcd),
+            (3, "Process Code", Fixed, 6, Bcd),
+            (4, "Transaction Amount", Fixed, 12, Bcd),
+            (5, "Settlement Amount", Fixed, 12, Bcd),
+            (6, "Cardholder Billing Amount", Fixed, 12, Bcd),
+            (7, "Transaction Date and Time", Fixed, 10, Bcd),
+            (9, "Conversion rate, settlement", Fixed, 8, Bcd),
+            (10, "Conversion rate, cardholder billing", Fixed, 8, Bcd),
+            (11, "Trace", Fixed, 6, Bcd),
+            (12, "Time", Fixed, 6, Bcd),
+            (13, "Date", Fixed, 4, Bcd),
+            (14, "Card EXpiration Date", Fixed, 4, Bcd),
+            (18, "Merchant Category Code", Fixed, 4, Bcd),
+            (19, "Acquirer Country Code", Fixed, 3, Bcd),
+            (22, "POS Entry Mode", Fixed, 4, Bcd),
+            (23, "Card Sequence Number", Fixed, 3, Bcd),
+            (24, "Network International ID", Fixed, 4, Bcd),
+            (25, "POS Condition Code", Fixed, 2, Bcd),
+            (33, "Forwarding Institution ID", LLVar, 11, Bcd),
+            (34, "Extended PAN", LLVar, 28, Ascii),
+            (35, "Track2", LLVar, 38, Bcd),
+            (36, "Track 3 Data", LLLVar, 104, Bcd),
+            (37, "Retrieval Ref #", Fixed, 12, Ascii),
+            (38, "Authorization Code", Fixed, 6, Ascii),
+            (39, "Response Code", Fixed, 2, Binary),
+            (41, "Terminal", Fixed, 8, Ascii),
+            (42, "Acceptor", Fixed, 15, Ascii),
+            (43, "Card Acceptor Name/Location", Fixed, 40, Ascii),
+            (44, "Additional response data", LLLVar, 25, Ascii),
+            (45, "Track 1 Data", LLVar, 76, Bcd),
+            (46, "Additional Data (ISO)", LLLVar, 999, Ascii),
+            (47, "Additional Data (National)", LLLVar, 999, Ascii),
+            (48, "Aditional Data", LLLVar, 999, Binary),
+            (49, "Transaction Currency Code", Fixed, 3, Ascii),
+            (50, "Settlement Currency Code", Fixed, 3, Ascii),
+            (51, "Billing Currency Code", Fixed, 3, Ascii),
+            (52, "PIN Block (encrypted)", Fixed, 8, Binary),
+            (53, "Security Control Info", Fixed, 16, Bcd),
+            (54, "Amount", LLLVar, 120, Binary),
+            (55, "ICC/EMV Data", LLLVar, 999, Binary),
+            (56, "Reserved (ISO)", LLLVar, 999, Ascii),
+            (57, "Reserved (National)", LLLVar, 999, Ascii),
+            (58, "Reserved (National)", LLLVar, 999, Ascii),
+            (59, "Reserved (National)", LLLVar, 999, Ascii),
+            (60, "Reserved (National)", LLLVar, 999, Binary),
+            (61, "Reserved (Private)", LLLVar, 999, Ascii),
+            (62, "Private", LLLVar, 999, Ascii),
+            (63, "Reserved (Private)", LLLVar, 999, Ascii),
+            (64, "MAC", Fixed, 8, Binary),
+            (66, "Settlement Code", Fixed, 1, Bcd),
+            (67, "Extended Payment Code", Fixed, 2, Bcd),
+            (68, "Receiving Institution Country Code", Fixed, 3, Bcd),
+            (69, "Settlement Institution Country Code", Fixed, 3, Bcd),
+            (70, "Network Management Info Code", Fixed, 4, Bcd),
+            (71, "Message Number", Fixed, 4, Bcd),
+            (72, "Message Number, Last", Fixed, 4, Bcd),
+            (73, "Date, Action", Fixed, 6, Bcd),
+            (74, "Credits, Number", Fixed, 10, Bcd),
+            (75, "Credits, Reversal Number", Fixed, 10, Bcd),
+            (76, "Debits, Number", Fixed, 10, Bcd),
+            (77, "Debits, Reversal Number", Fixed, 10, Bcd),
+            (78, "Transfers, Number", Fixed, 10, Bcd),
+            (79, "Transfers, Reversal Number", Fixed, 10, Bcd),
+            (80, "Inquiries, Number", Fixed, 10, Bcd),
+            (81, "Authorizations, Number", Fixed, 10, Bcd),
+            (82, "Credits, Processing Fee Amount", Fixed, 12, Bcd),
+            (83, "Credits, Transaction Fee Amount", Fixed, 12, Bcd),
+            (84, "Debits, Processing Fee Amount", Fixed, 12, Bcd),
+            (85, "Debits, Transaction Fee Amount", Fixed, 12, Bcd),
+            (86, "Credits, Amount", Fixed, 16, Bcd),
+            (87, "Credits, Reversal Amount", Fixed, 16, Bcd),
+            (88, "Debits, Amount", Fixed, 16, Bcd),
+            (89, "Debits, Reversal Amount", Fixed, 16, Bcd),
+            (90, "Original Data Elements", Fixed, 42, Bcd),
+            (100, "Receiving Institution ID", LLVar, 11, Ascii),
+            (102, "Account Identification 1", LLVar, 28, Ascii),
+            (103, "Account Identification 2", LLVar, 28, Ascii),
+            (116, "Reserved (National)", LLLVar, 999, Ascii),
+            (121, "Private Data", LLLVar, 999, Binary),
+            (122, "Additional Data", LLLVar, 999, Ascii),
+            (128, "MAC", Fixed, 8, Binary),
+        ];
+        let mut catalog = FieldCatalog::empty();
+        for (bit, name, length_type, max_length, encoding) in table {
+            catalog.insert(bit, FieldSpec::new(name, length_type, max_length, encoding));
+        }
+        // Fields of the tertiary bitmap are only used by private specs
+        for bit in 130..=192 {
+            catalog.insert(bit, FieldSpec::new("Reserved (Tertiary)", LLLVar, 999, Binary));
+        }
+        catalog
+    }
+
+    /// Field definitions of ISO 8583:1993, also used for 2003 messages.
+    ///
+    /// They differ from 1987 in the fields whose length changed between the versions,
+    /// e.g. the 3-digit action code of field 39.
+    pub fn iso1993() -> Self {
+        use Encoding::*;
+        use LengthType::*;
+        let mut catalog = FieldCatalog::iso1987();
+        let changes = [
+            (12, "Date and Time, Local", Fixed, 12, Bcd),
+            (24, "Function Code", Fixed, 3, Bcd),
+            (25, "Message Reason Code", Fixed, 4, Bcd),
+            // The action code is n3, sent as ASCII digits like the response code of 1987
+            (39, "Action Code", Fixed, 3, Ascii),
+            // ISO 8583:1993 defines field 44 as LLVAR ans..99, where 1987 allows an..25
+            (44, "Additional response data", LLVar, 99, Ascii),
+        ];
+        for (bit, name, length_type, max_length, encoding) in changes {
+            catalog.insert(bit, FieldSpec::new(name, length_type, max_length, encoding));
+        }
+        catalog
+    }
+
+    /// Field definitions matching the version of a message type indicator: 1993 for MTIs
+    /// starting with 1 or 2 and 1987 for all others.
+    pub fn for_mti(mti: &str) -> Self {
+        match mti.chars().next() {
+            Some('1') | Some('2') => FieldCatalog::iso1993(),
+            _ => FieldCatalog::iso1987(),
+        }
+    }
+}
+
+/// The four components of a message type indicator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DecodedMti {
+    pub version: String,
+    pub class: String,
+    pub function: String,
+    pub origin: String,
+}
+
+/// Decode a message type indicator into its version, class, function and origin.
+pub fn decode_mti(mti: &str) -> Result<DecodedMti, Iso8583Error> {
+    let digits = mti.chars().map(|c| c.to_digit(10)).collect::<Option<Vec<u32>>>();
+    let digits = match digits {
+        Some(digits) if digits.len() == 4 => digits,
+        _ => return Err(Iso8583Error::InvalidMti(mti.to_string())),
+    };
+    let version = match digits[0] {
+        0 => "1987",
+        1 => "1993",
+        2 => "2003",
+        8 => "National Use",
+        9 => "Private Use",
+        _ => "Reserved",
+    };
+    let class = match digits[1] {
+        1 => "Authorization",
+        2 => "Financial",
+        3 => "File Actions",
+        4 => "Reversal/Chargeback",
+        5 => "Reconciliation",
+        6 => "Administrative",
+        7 => "Fee Collection",
+        8 => "Network Management",
+        _ => "Reserved",
+    };
+    let function = match digits[2] {
+        0 => "Request",
+        1 => "Request Response",
+        2 => "Advice",
+        3 => "Advice Response",
+        4 => "Notification",
+        5 => "Notification Acknowledgement",
+        6 => "Instruction",
+        7 => "Instruction Acknowledgement",
+        _ => "Reserved",
+    };
+    let origin = match digits[3] {
+        0 => "Acquirer",
+        1 => "Acquirer Repeat",
+        2 => "Issuer",
+        3 => "Issuer Repeat",
+        4 => "Other",
+        5 => "Other Repeat",
+        _ => "Reserved",
+    };
+    Ok(DecodedMti {
+        version: version.to_string(),
+        class: class.to_string(),
+        function: function.to_string(),
+        origin: origin.to_string(),
+    })
+}
+
+/// The subfields of field 3 (processing code).
+///
+/// Each subfield holds its description, or the raw two digits when the code is unknown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProcessingCode {
+    pub transaction_type: String,
+    pub from_account: String,
+    pub to_account: String,
+}
+
+/// Parse field 3 into transaction type, account type from and account type to.
+pub fn parse_processing_code(code: &str) -> Result<ProcessingCode, Iso8583Error> {
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Iso8583Error::InvalidField { bit: 3, reason: format!("invalid processing code {:?}", code) });
+    }
+    let transaction_type = match &code[..2] {
+        "00" => "Purchase",
+        "01" => "Withdrawal",
+        "02" => "Debit Adjustment",
+        "09" => "Purchase with Cashback",
+        "11" => "Quasi-Cash",
+        "20" => "Refund",
+        "21" => "Deposit",
+        "22" => "Credit Adjustment",
+        "28" => "Payment",
+        "30" => "Available Funds Inquiry",
+        "31" => "Balance Inquiry",
+        "40" => "Cardholder Accounts Transfer",
+        "50" => "Bill Payment",
+        other => other,
+    };
+    let account_type = |digits: &str| match digits {
+        "00" => "Default".to_string(),
+        "10" => "Savings".to_string(),
+        "20" => "Checking".to_string(),
+        "30" => "Credit".to_string(),
+        "40" => "Universal".to_string(),
+        other => other.to_string(),
+    };
+    Ok(ProcessingCode {
+        transaction_type: transaction_type.to_string(),
+        from_account: account_type(&code[2..4]),
+        to_account: account_type(&code[4..]),
+    })
+}
+
+/// The components of Track 2 data (field 35).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Track2 {
+    pub pan: String,
+    /// Expiry date in YYMM format.
+    pub expiry: String,
+    pub service_code: String,
+    pub discretionary_data: String,
+}
+
+/// Parse Track 2 data into its components.
+///
+/// The PAN is separated from the rest by `=` or `D`. A trailing `F` used to pad
+/// the data to a whole number of bytes is ignored.
+pub fn parse_track2(data: &str) -> Result<Track2, Iso8583Error> {
+    let data = data.strip_suffix(['F', 'f']).unwrap_or(data);
+    let (pan, rest) = data
+        .split_once(['=', 'D', 'd'])
+        .ok_or_else(|| Iso8583Error::InvalidField { bit: 35, reason: format!("no separator found in {:?}", data) })?;
+    if rest.len() < 7 {
+        return Err(Iso8583Error::InvalidField { bit: 35, reason: format!("data is too short after the separator: {:?}", rest) });
+    }
+    Ok(Track2 {
+        pan: pan.to_string(),
+        expiry: rest[..4].to_string(),
+        service_code: rest[4..7].to_string(),
+        discretionary_data: rest[7..].to_string(),
+    })
+}
+
+/// The components of the original data elements (field 90) of reversals and advices,
+/// identifying the message they refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OriginalDataElements {
+    pub mti: String,
+    pub stan: String,
+    /// Transmission date and time in MMDDhhmmss format.
+    pub transmission_date_time: String,
+    pub acquirer_id: String,
+    pub forwarding_id: String,
+}
+
+/// Parse the 42 digits of the original data elements (field 90).
+pub fn parse_original_data_elements(data: &str) -> Result<OriginalDataElements, Iso8583Error> {
+    if data.len() != 42 || !data.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Iso8583Error::InvalidField { bit: 90, reason: format!("expected 42 digits, got {:?}", data) });
+    }
+    Ok(OriginalDataElements {
+        mti: data[..4].to_string(),
+        stan: data[4..10].to_string(),
+        transmission_date_time: data[10..20].to_string(),
+        acquirer_id: data[20..31].to_string(),
+        forwarding_id: data[31..].to_string(),
+    })
+}
+
+/// Check the Luhn checksum of a PAN, ignoring any non-digit character.
+///
+/// Returns `false` when the PAN does not have between 12 and 19 digits.
+pub fn luhn_is_valid(pan: &str) -> bool {
+    let digits: Vec<u32> = pan.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(12..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn luhn_status(pan: &str) -> &'static str {
+    if luhn_is_valid(pan) { "(Luhn: OK)" } else { "(Luhn: FAIL)" }
+}
+
+/// Replace all digits of a PAN but the first 6 and the last 4 with `*`.
+pub fn mask_pan(pan: &str) -> String {
+    let digit_count = pan.chars().filter(|c| c.is_ascii_digit()).count();
+    let mut digit_index = 0;
+    pan.chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            digit_index += 1;
+            if digit_index > 6 && digit_index + 4 <= digit_count { '*' } else { c }
+        })
+        .collect()
+}
+
+/// Mask the PAN of Track 2 data, which ends at the `=` or `D` separator.
+fn mask_track2(data: &str) -> String {
+    match data.find(['=', 'D', 'd']) {
+        Some(index) => format!("{}{}", mask_pan(&data[..index]), &data[index..]),
+        None => mask_pan(data),
+    }
+}
+
+/// Mask the PAN of Track 1 data, which lies between the format code and the first `^`.
+/// Hex encoded Track 1 data is decoded, masked and encoded again.
+fn mask_track1(data: &str) -> String {
+    if let Some(end) = data.find('^') {
+        let start = data[..end].rfind(|c: char| !c.is_ascii_digit() && c != ' ').map_or(0, |i| i + 1);
+        return format!("{}{}{}", &data[..start], mask_pan(&data[start..end]), &data[end..]);
+    }
+    match data.to_string().hex_to_ascii() {
+        Ok(ascii) if ascii.contains('^') => hex::encode_upper(mask_track1(&ascii)),
+        _ => data.to_string(),
+    }
+}
+
+/// Split `raw` into 2 digit numbers and check each one is within its range.
+fn parse_date_time(bit: u32, raw: &str, ranges: &[(&str, core::ops::RangeInclusive<u32>)]) -> Result<Vec<String>, Iso8583Error> {
+    if raw.len() != ranges.len() * 2 || !raw.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Iso8583Error::InvalidField { bit, reason: format!("expected {} digits but got {:?}", ranges.len() * 2, raw) });
+    }
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(i, (name, range))| {
+            let part = &raw[i * 2..i * 2 + 2];
+            if range.contains(&part.parse::<u32>()?) {
+                Ok(part.to_string())
+            } else {
+                Err(Iso8583Error::InvalidField { bit, reason: format!("{} {} is out of range", name, part) })
+            }
+        })
+        .collect()
+}
+
+/// Format field 7 (transmission date and time, MMDDhhmmss) as `MM-DD hh:mm:ss`.
+pub fn format_field7(raw: &str) -> Result<String, Iso8583Error> {
+    let parts = parse_date_time(7, raw, &[("month", 1..=12), ("day", 1..=31), ("hour", 0..=23), ("minute", 0..=59), ("second", 0..=59)])?;
+    Ok(format!("{}-{} {}:{}:{}", parts[0], parts[1], parts[2], parts[3], parts[4]))
+}
+
+/// Format field 12 (local transaction time, hhmmss) as `hh:mm:ss`, or its 1993 form
+/// (local date and time, YYMMDDhhmmss) as `YY-MM-DD hh:mm:ss`.
+pub fn format_field12(raw: &str) -> Result<String, Iso8583Error> {
+    if raw.len() == 12 {
+        let parts = parse_date_time(12, raw, &[("year", 0..=99), ("month", 1..=12), ("day", 1..=31), ("hour", 0..=23), ("minute", 0..=59), ("second", 0..=59)])?;
+        return Ok(format!("{}-{}-{} {}:{}:{}", parts[0], parts[1], parts[2], parts[3], parts[4], parts[5]));
+    }
+    let parts = parse_date_time(12, raw, &[("hour", 0..=23), ("minute", 0..=59), ("second", 0..=59)])?;
+    Ok(format!("{}:{}:{}", parts[0], parts[1], parts[2]))
+}
+
+/// Format field 13 (local transaction date, MMDD) as `MM-DD`.
+pub fn format_field13(raw: &str) -> Result<String, Iso8583Error> {
+    let parts = parse_date_time(13, raw, &[("month", 1..=12), ("day", 1..=31)])?;
+    Ok(format!("{}-{}", parts[0], parts[1]))
+}
+
+/// Number of minor unit digits of a currency given by its ISO 4217 alpha or numeric code.
+fn currency_exponent(currency: &str) -> usize {
+    match currency {
+        "JPY" | "392" | "KRW" | "410" | "VND" | "704" | "CLP" | "152" | "ISK" | "352" => 0,
+        "BHD" | "048" | "KWD" | "414" | "JOD" | "400" | "OMR" | "512" | "TND" | "788" | "IQD" | "368" | "LYD" | "434" => 3,
+        _ => 2,
+    }
+}
+
+/// Numeric and alphabetic ISO 4217 codes of the currencies in use.
+const CURRENCIES: &[(&str, &str)] = &[
+    ("008", "ALL"), ("012", "DZD"), ("032", "ARS"), ("036", "AUD"), ("044", "BSD"), ("048", "BHD"), ("050", "BDT"), ("051", "AMD"),
+    ("052", "BBD"), ("060", "BMD"), ("064", "BTN"), ("068", "BOB"), ("072", "BWP"), ("084", "BZD"), ("090", "SBD"), ("096", "BND"),
+    ("104", "MMK"), ("108", "BIF"), ("116", "KHR"), ("124", "CAD"), ("132", "CVE"), ("136", "KYD"), ("144", "LKR"), ("152", "CLP"),
+    ("156", "CNY"), ("170", "COP"), ("174", "KMF"), ("188", "CRC"), ("192", "CUP"), ("203", "CZK"), ("208", "DKK"), ("214", "DOP"),
+    ("222", "SVC"), ("230", "ETB"), ("232", "ERN"), ("238", "FKP"), ("242", "FJD"), ("262", "DJF"), ("270", "GMD"), ("292", "GIP"),
+    ("320", "GTQ"), ("324", "GNF"), ("328", "GYD"), ("332", "HTG"), ("340", "HNL"), ("344", "HKD"), ("348", "HUF"), ("352", "ISK"),
+    ("356", "INR"), ("360", "IDR"), ("364", "IRR"), ("368", "IQD"), ("376", "ILS"), ("388", "JMD"), ("392", "JPY"), ("398", "KZT"),
+    ("400", "JOD"), ("404", "KES"), ("408", "KPW"), ("410", "KRW"), ("414", "KWD"), ("417", "KGS"), ("418", "LAK"), ("422", "LBP"),
+    ("426", "LSL"), ("430", "LRD"), ("434", "LYD"), ("446", "MOP"), ("454", "MWK"), ("458", "MYR"), ("462", "MVR"), ("480", "MUR"),
+    ("484", "MXN"), ("496", "MNT"), ("498", "MDL"), ("504", "MAD"), ("512", "OMR"), ("516", "NAD"), ("524", "NPR"), ("532", "ANG"),
+    ("533", "AWG"), ("548", "VUV"), ("554", "NZD"), ("558", "NIO"), ("566", "NGN"), ("578", "NOK"), ("586", "PKR"), ("590", "PAB"),
+    ("598", "PGK"), ("600", "PYG"), ("604", "PEN"), ("608", "PHP"), ("634", "QAR"), ("643", "RUB"), ("646", "RWF"), ("654", "SHP"),
+    ("682", "SAR"), ("690", "SCR"), ("702", "SGD"), ("704", "VND"), ("706", "SOS"), ("710", "ZAR"), ("728", "SSP"), ("748", "SZL"),
+    ("752", "SEK"), ("756", "CHF"), ("760", "SYP"), ("764", "THB"), ("776", "TOP"), ("780", "TTD"), ("784", "AED"), ("788", "TND"),
+    ("800", "UGX"), ("807", "MKD"), ("818", "EGP"), ("826", "GBP"), ("834", "TZS"), ("840", "USD"), ("858", "UYU"), ("860", "UZS"),
+    ("882", "WST"), ("886", "YER"), ("901", "TWD"), ("925", "SLE"), ("926", "VED"), ("928", "VES"), ("929", "MRU"), ("930", "STN"),
+    ("933", "BYN"), ("934", "TMT"), ("936", "GHS"), ("938", "SDG"), ("941", "RSD"), ("943", "MZN"), ("944", "AZN"), ("946", "RON"),
+    ("949", "TRY"), ("950", "XAF"), ("951", "XCD"), ("952", "XOF"), ("953", "XPF"), ("967", "ZMW"), ("968", "SRD"), ("969", "MGA"),
+    ("971", "AFN"), ("972", "TJS"), ("973", "AOA"), ("975", "BGN"), ("976", "CDF"), ("977", "BAM"), ("978", "EUR"), ("980", "UAH"),
+    ("981", "GEL"), ("985", "PLN"), ("986", "BRL"),
+];
+
+/// Look up the alphabetic ISO 4217 code of a numeric currency code, e.g. `840` as `USD`.
+pub fn currency_alpha_code(numeric: &str) -> Option<&'static str> {
+    CURRENCIES.iter().find(|(n, _)| *n == numeric).map(|(_, alpha)| *alpha)
+}
+
+/// Flags of the Terminal Verification Results (EMV tag 95) per EMV Book 3 annex C5, from the
+/// most significant bit of the first byte on. RFU bits are empty.
+const TVR_FLAGS: [&str; 40] = [
+    "Offline data authentication was not performed",
+    "SDA failed",
+    "ICC data missing",
+    "Card appears on terminal exception file",
+    "DDA failed",
+    "CDA failed",
+    "SDA selected",
+    "",
+    "ICC and terminal have different application versions",
+    "Expired application",
+    "Application not yet effective",
+    "Requested service not allowed for card product",
+    "New card",
+    "",
+    "",
+    "",
+    "Cardholder verification was not successful",
+    "Unrecognised CVM",
+    "PIN Try Limit exceeded",
+    "PIN entry required and PIN pad not present or not working",
+    "PIN entry required, PIN pad present, but PIN was not entered",
+    "Online PIN entered",
+    "",
+    "",
+    "Transaction exceeds floor limit",
+    "Lower consecutive offline limit exceeded",
+    "Upper consecutive offline limit exceeded",
+    "Transaction selected randomly for online processing",
+    "Merchant forced transaction online",
+    "",
+    "",
+    "",
+    "Default TDOL used",
+    "Issuer authentication failed",
+    "Script processing failed before final GENERATE AC",
+    "Script processing failed after final GENERATE AC",
+    "",
+    "",
+    "",
+    "",
+];
+
+/// List the flags set in a Terminal Verification Results (EMV tag 95) value.
+pub fn decode_tvr(bytes: &[u8]) -> Vec<&'static str> {
+    positions_of_set_bits_bytes(bytes)
+        .into_iter()
+        .filter_map(|bit| TVR_FLAGS.get(bit as usize - 1).copied())
+        .filter(|flag| !flag.is_empty())
+        .collect()
+}
+
+/// Format an amount with the implied decimal places of its currency, e.g. `000000012345` as `123.45`.
+///
+/// Currencies default to 2 decimal places. Non numeric amounts are returned unchanged.
+pub fn format_amount(raw: &str, currency: Option<&str>) -> String {
+    if raw.is_empty() || !raw.chars().all(|c| c.is_ascii_digit()) {
+        return raw.to_string();
+    }
+    let exponent = currency.map_or(2, currency_exponent);
+    let digits = raw.trim_start_matches('0');
+    let digits = format!("{:0>width$}", digits, width = exponent + 1);
+    let (units, minor_units) = digits.split_at(digits.len() - exponent);
+    if exponent == 0 {
+        units.to_string()
+    } else {
+        format!("{}.{}", units, minor_units)
+    }
+}
+
+/// Format the additional amounts of field 54, made of 20 character blocks holding the
+/// account type, amount type, currency code, sign and amount.
+fn format_additional_amounts(value: &str) -> Result<Vec<String>, Iso8583Error> {
+    if value.is_empty() || !value.len().is_multiple_of(20) || !value.is_ascii() {
+        return Err(Iso8583Error::InvalidField { bit: 54, reason: format!("invalid additional amounts {:?}", value) });
+    }
+    Ok((0..value.len())
+        .step_by(20)
+        .map(|i| {
+            let block = &value[i..i + 20];
+            let sign = if &block[7..8] == "D" { "-" } else { "" };
+            format!(
+                "\tAccount Type: {} | Amount Type: {} | Currency: {} | Amount: {}{}",
+                &block[..2],
+                &block[2..4],
+                &block[4..7],
+                sign,
+                format_amount(&block[8..], Some(&block[4..7])),
+            )
+        })
+        .collect())
+}
+
+/// The components of field 22 (POS entry mode).
+///
+/// Each component holds its description, or the raw digits when the code is unknown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PosEntryMode {
+    pub pan_entry_mode: String,
+    pub pin_entry_capability: String,
+}
+
+/// Parse field 22 into PAN entry mode and PIN entry capability.
+///
+/// Both the 3 digit form and the 4 digit form padded with a leading zero are accepted.
+pub fn parse_pos_entry_mode(code: &str) -> Result<PosEntryMode, Iso8583Error> {
+    if !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Iso8583Error::InvalidField { bit: 22, reason: format!("invalid POS entry mode {:?}", code) });
+    }
+    let code = match code.len() {
+        3 => code,
+        4 => &code[1..],
+        _ => return Err(Iso8583Error::InvalidField { bit: 22, reason: format!("invalid POS entry mode {:?}", code) }),
+    };
+    let pan_entry_mode = match &code[..2] {
+        "00" => "Unknown",
+        "01" => "Manual",
+        "02" => "Magnetic Stripe",
+        "03" => "Bar Code",
+        "04" => "OCR",
+        "05" => "Chip",
+        "07" => "Contactless Chip",
+        "10" => "Credential on File",
+        "79" => "Chip Fallback to Manual",
+        "80" => "Chip Fallback to Magnetic Stripe",
+        "81" => "E-Commerce",
+        "90" => "Magnetic Stripe (Full Track)",
+        "91" => "Contactless Magnetic Stripe",
+        "95" => "Chip (Unreliable CVV)",
+        other => other,
+    };
+    let pin_entry_capability = match &code[2..] {
+        "0" => "Unknown PIN capability",
+        "1" => "PIN capable",
+        "2" => "Not PIN capable",
+        "8" => "PIN pad inoperative",
+        other => other,
+    };
+    Ok(PosEntryMode {
+        pan_entry_mode: pan_entry_mode.to_string(),
+        pin_entry_capability: pin_entry_capability.to_string(),
+    })
+}
+
+/// The subfields of field 43 (card acceptor name/location).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CardAcceptorLocation {
+    pub name: String,
+    pub city: String,
+    pub state_or_country: String,
+}
+
+/// Slice field 43 into name (positions 1-25), city (26-38) and state or country code (39-40),
+/// trimming trailing spaces. Data shorter than 40 characters is padded with spaces.
+pub fn parse_card_acceptor_location(data: &str) -> Result<CardAcceptorLocation, Iso8583Error> {
+    if data.chars().count() > 40 {
+        return Err(Iso8583Error::InvalidField { bit: 43, reason: format!("expected at most 40 characters but got {:?}", data) });
+    }
+    let data: Vec<char> = format!("{:<40}", data).chars().collect();
+    let part = |range: core::ops::Range<usize>| data[range].iter().collect::<String>().trim_end().to_string();
+    Ok(CardAcceptorLocation { name: part(0..25), city: part(25..38), state_or_country: part(38..40) })
+}
+
+/// Describe a response code (field 39).
+///
+/// Two character codes are looked up in the ISO 8583:1987 table and three digit codes
+/// in the ISO 8583:1993 table.
+pub fn describe_response_code(code: &str) -> Option<&'static str> {
+    let description = match code {
+        "00" => "Approved",
+        "01" => "Refer to Card Issuer",
+        "02" => "Refer to Card Issuer, Special Condition",
+        "03" => "Invalid Merchant",
+        "04" => "Pick Up Card",
+        "05" => "Do Not Honor",
+        "06" => "Error",
+        "07" => "Pick Up Card, Special Condition",
+        "08" => "Honor with Identification",
+        "10" => "Approved for Partial Amount",
+        "11" => "Approved (VIP)",
+        "12" => "Invalid Transaction",
+        "13" => "Invalid Amount",
+        "14" => "Invalid Card Number",
+        "15" => "No Such Issuer",
+        "19" => "Re-enter Transaction",
+        "21" => "No Action Taken",
+        "25" => "Unable to Locate Record",
+        "30" => "Format Error",
+        "41" => "Lost Card",
+        "43" => "Stolen Card",
+        "51" => "Insufficient Funds",
+        "54" => "Expired Card",
+        "55" => "Incorrect PIN",
+        "57" => "Transaction Not Permitted to Cardholder",
+        "58" => "Transaction Not Permitted to Terminal",
+        "59" => "Suspected Fraud",
+        "61" => "Exceeds Withdrawal Amount Limit",
+        "62" => "Restricted Card",
+        "63" => "Security Violation",
+        "65" => "Exceeds Withdrawal Frequency Limit",
+        "68" => "Response Received Too Late",
+        "75" => "Allowable Number of PIN Tries Exceeded",
+        "76" => "Invalid Account",
+        "91" => "Issuer Unavailable",
+        "92" => "Financial Institution Cannot Be Found",
+        "94" => "Duplicate Transmission",
+        "96" => "System Malfunction",
+        "000" => "Approved",
+        "001" => "Honor with Identification",
+        "002" => "Approved for Partial Amount",
+        "003" => "Approved (VIP)",
+        "100" => "Do Not Honor",
+        "101" => "Expired Card",
+        "102" => "Suspected Fraud",
+        "106" => "Allowable Number of PIN Tries Exceeded",
+        "107" => "Refer to Card Issuer",
+        "108" => "Refer to Card Issuer, Special Condition",
+        "110" => "Invalid Amount",
+        "111" => "Invalid Card Number",
+        "116" => "Insufficient Funds",
+        "117" => "Incorrect PIN",
+        "119" => "Transaction Not Permitted to Cardholder",
+        "120" => "Transaction Not Permitted to Terminal",
+        "121" => "Exceeds Withdrawal Amount Limit",
+        "123" => "Exceeds Withdrawal Frequency Limit",
+        "200" => "Pick Up Card",
+        "208" => "Lost Card",
+        "209" => "Stolen Card",
+        "400" => "Accepted (Reversal)",
+        "800" => "Accepted (Network Management)",
+        "902" => "Invalid Transaction",
+        "904" => "Format Error",
+        "907" => "Issuer Unavailable",
+        "909" => "System Malfunction",
+        "911" => "Issuer Timed Out",
+        "913" => "Duplicate Transmission",
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// Whether a response code approves, refers or declines the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseCategory {
+    Approved,
+    /// The acquirer has to call the issuer before the transaction can be approved.
+    Referral,
+    Declined,
+}
+
+/// Response codes of `describe_response_code` approving the transaction, 1987 and 1993.
+const APPROVED_RESPONSE_CODES: [&str; 10] = ["00", "08", "10", "11", "000", "001", "002", "003", "400", "800"];
+
+/// Response codes of `describe_response_code` asking to refer to the issuer, 1987 and 1993.
+const REFERRAL_RESPONSE_CODES: [&str; 4] = ["01", "02", "107", "108"];
+
+/// Tell whether a response code approves, refers or declines the transaction. Unknown codes
+/// are taken as declines.
+pub fn response_category(code: &str) -> ResponseCategory {
+    if APPROVED_RESPONSE_CODES.contains(&code) {
+        ResponseCategory::Approved
+    } else if REFERRAL_RESPONSE_CODES.contains(&code) {
+        ResponseCategory::Referral
+    } else {
+        ResponseCategory::Declined
+    }
+}
+
+/// Describe a network management information code (field 70) of an 08xx message.
+///
+/// The code has 3 digits; the 4 digits of field 70 in the default catalog are accepted with
+/// their leading zero.
+pub fn describe_network_mgmt(code: &str) -> Option<&'static str> {
+    let code = match code.len() {
+        4 => code.strip_prefix('0')?,
+        _ => code,
+    };
+    let description = match code {
+        "001" => "Sign-on",
+        "002" => "Sign-off",
+        "101" => "Key Change",
+        "161" => "New Key Request",
+        "201" => "Cutover",
+        "301" => "Echo Test",
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// Get the response code out of the value of field 39, which may hold the code
+/// itself or its hex encoded ASCII characters.
+fn response_code(value: &str) -> String {
+    match value.to_string().hex_to_ascii() {
+        Ok(code) if (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric()) => code,
+        _ => value.to_string(),
+    }
+}
+
+/// A single parsed field of an ISO8583 message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParsedField {
+    pub number: u32,
+    pub name: String,
+    pub length: u32,
+    pub value: String,
+    /// The field as it appeared on the wire, kept for text fields whose value was decoded from hex.
+    pub raw: Option<String>,
+    /// Decoded sub-elements of the field (EMV tags, private TLV/LTV) and their parsing errors.
+    pub details: Vec<String>,
+    /// Offset of the field from the start of the message, in hex characters, including its
+    /// length prefix. Only set by `parse_iso8583_bytes`; it is 0 for a field processed on its own.
+    pub start_offset: usize,
+    /// Offset just past the end of the field, in hex characters.
+    pub end_offset: usize,
+    /// Anomalies of the field, such as a PAN failing the Luhn check. They are also gathered,
+    /// prefixed with the field number, in `ParserResult::warnings`.
+    pub warnings: Vec<String>,
+    /// The private TLV/LTV elements of fields 48 and 121, when they were parsed, and the EMV
+    /// tags of field 55 processed on its own. `parse_iso8583_bytes` moves the EMV tags to
+    /// `ParserResult::emv_tags`.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub sub_elements: Option<SubElements>,
+}
+
+/// The private elements a field was parsed into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SubElements {
+    Ltv(Vec<LTV>),
+    PrivateTlv(Vec<PrivateTlv>),
+    Emv(Vec<EmvTag>),
+}
+
+/// Whether a character is shown as it is in the text of a value: printable ASCII, from the
+/// space to `~`. The others are shown as `.`.
+fn is_printable(c: char) -> bool {
+    (' '..='~').contains(&c)
+}
+
+/// Replace the characters of a text that are not printable with `.`.
+fn printable_text(text: &str) -> String {
+    text.chars().map(|c| if is_printable(c) { c } else { '.' }).collect()
+}
+
+impl ParsedField {
+    /// Byte offset of the field from the start of the message, including its length prefix.
+    pub fn offset(&self) -> usize {
+        self.start_offset / 2
+    }
+
+    /// Number of bytes of the message the field takes, including its length prefix. A field
+    /// ending in the middle of a byte, like an odd number of BCD digits, rounds up.
+    pub fn len_consumed(&self) -> usize {
+        self.end_offset.div_ceil(2) - self.offset()
+    }
+
+    /// Whether the number or the name of the field contains `query`, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.number.to_string().contains(&query) || self.name.to_lowercase().contains(&query)
+    }
+
+    /// Format the field and its details, one line each.
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let text = if options.printable { printable_text(&self.value) } else { self.value.clone() };
+        let value = match &self.raw {
+            Some(raw) if options.show_raw => format!("{} -> {}", raw, text),
+            _ => text,
+        };
+        let offsets = if options.show_offsets {
+            format!(" Offset: {:4}..{:4}{}", self.start_offset, self.end_offset, options.separator)
+        } else {
+            String::new()
+        };
+        let mut output = format!(
+            "Field {:3} {sep} Length: {:3}{sep}{} {:width$} {sep} {}\n",
+            self.number,
+            self.length,
+            offsets,
+            self.name,
+            value,
+            sep = options.separator,
+            width = options.name_width,
+        );
+        for detail in &self.details {
+            output.push_str(detail);
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// A tag of the EMV data of field 55.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EmvTag {
+    /// The tag in hex, e.g. `9F26`.
+    pub tag: String,
+    pub name: String,
+    pub length: usize,
+    pub value: Vec<u8>,
+    /// How deep the tag is nested in constructed tags, 0 for top level tags.
+    pub nest_level: usize,
+}
+
+impl EmvTag {
+    /// One line of the details of field 55: the tag, its length, its name and its value, in
+    /// ASCII too when it is printable. The hex is written in `hex_case`.
+    fn detail(&self, hex_case: HexCase) -> String {
+        let tag = hex_case.apply(&self.tag);
+        let tag: Vec<&str> = (0..tag.len()).step_by(2).map(|i| &tag[i..i + 2]).collect();
+        let text = if self.value.iter().all(|&byte| is_printable(byte as char)) && !self.value.is_empty() {
+            format!("-> {}", self.value.iter().map(|&byte| byte as char).collect::<String>())
+        } else {
+            String::new()
+        };
+        let value = hex_case.apply(&hex::encode(&self.value));
+        format!("{}  {:5} | {:3} | {} | {} {}", "  ".repeat(self.nest_level), tag.join(" "), self.length, self.name, value, text)
+    }
+}
+
+impl fmt::Display for EmvTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.detail(HexCase::Upper))
+    }
+}
+
+/// Result of parsing an ISO8583 message.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParserResult {
+    pub message_length: Option<u32>,
+    pub header: Option<String>,
+    pub mti: String,
+    /// The decoded MTI, or `None` when the MTI is not made of 4 digits, which is only accepted
+    /// by lenient parsing.
+    pub decoded_mti: Option<DecodedMti>,
+    pub bitmap: Vec<u32>,
+    /// The bitmaps in hex, rebuilt from `bitmap` by `bits_to_bitmap_hex`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bitmap_hex: String,
+    pub fields: Vec<ParsedField>,
+    pub unparsed: String,
+    /// The MAC of field 64 or 128, when the message carries one.
+    pub mac: Option<Vec<u8>>,
+    /// Anomalies found while parsing that did not prevent the message from being parsed.
+    pub warnings: Vec<String>,
+    /// The EMV tags of field 55, in message order with the tags of constructed tags following
+    /// them.
+    pub emv_tags: Vec<EmvTag>,
+    /// Whether the LRC byte following the message matched the message, when `Mode::lrc` is set.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub trailer_ok: Option<bool>,
+    /// The bytes from the MTI up to the MAC field, which the MAC is computed over.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mac_input: Vec<u8>,
+}
+
+impl IntoIterator for ParserResult {
+    type Item = ParsedField;
+    type IntoIter = alloc::vec::IntoIter<ParsedField>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ParserResult {
+    type Item = &'a ParsedField;
+    type IntoIter = core::slice::Iter<'a, ParsedField>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.iter()
+    }
+}
+
+/// A difference between two parsed messages, as found by `ParserResult::diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// What differs: `MTI`, `Bitmap` or `Field N`.
+    pub item: String,
+    /// The value in the first message, or `None` when it is missing from it.
+    pub left: Option<String>,
+    /// The value in the second message, or `None` when it is missing from it.
+    pub right: Option<String>,
+}
+
+/// Aggregate figures over a batch of parsed messages, as computed by `summarize`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BatchSummary {
+    /// Number of messages parsed.
+    pub messages: usize,
+    /// Number of messages that could not be parsed. `summarize` only sees parsed messages, so
+    /// the caller sets it.
+    pub errors: usize,
+    /// Number of messages by MTI.
+    pub by_mti: BTreeMap<String, usize>,
+    /// Number of messages by response code (field 39).
+    pub by_response_code: BTreeMap<String, usize>,
+    /// Number of messages with an amount (field 4).
+    pub amounts: usize,
+    /// Sum of the amounts of field 4, in minor units whatever their currency.
+    pub total_amount: u64,
+    /// Average of the amounts of field 4, rounded down, or `None` without amounts.
+    pub average_amount: Option<u64>,
+}
+
+/// MAC algorithms supported by `ParserResult::verify_mac`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacAlgo {
+    /// ISO 9797-1 MAC algorithm 3 (retail MAC) with a double length DES key and zero padding.
+    Retail,
+}
+
+/// Compute the longitudinal redundancy check of the data: the XOR of all its bytes.
+pub fn compute_lrc(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |lrc, byte| lrc ^ byte)
+}
+
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+
+/// The LRC byte of a message and the bytes it covers.
+type Lrc<'a> = Option<(u8, &'a [u8])>;
+
+/// Remove the STX at the start of `data` and the ETX at its end, along with the LRC that may
+/// follow the ETX. The LRC is returned when `lrc` requires one.
+fn strip_framing(data: &[u8], lrc: bool) -> Result<(&[u8], Lrc<'_>), Iso8583Error> {
+    let framed = &data[1..];
+    if lrc {
+        return match framed {
+            [covered @ .., lrc] if covered.last() == Some(&ETX) => Ok((&covered[..covered.len() - 1], Some((*lrc, covered)))),
+            _ => Err(Iso8583Error::InvalidFraming("the message starts with STX but no ETX and LRC end it".to_string())),
+        };
+    }
+    match framed {
+        [body @ .., last] if *last == ETX => Ok((body, None)),
+        [body @ .., etx, _] if *etx == ETX => Ok((body, None)),
+        _ => Err(Iso8583Error::InvalidFraming("the message starts with STX but has no ETX".to_string())),
+    }
+}
+
+/// Compute the ISO 9797-1 MAC algorithm 3 of the data, or `None` when the key is not 16 bytes long.
+fn retail_mac(key: &[u8], data: &[u8]) -> Option<[u8; 8]> {
+    if key.len() != 16 {
+        return None;
+    }
+    let k1 = Des::new_from_slice(&key[..8]).ok()?;
+    let k2 = Des::new_from_slice(&key[8..]).ok()?;
+    let mut block = [0u8; 8];
+    // Padding method 1: the last chunk is completed with zeros, which leaves the XOR untouched
+    for chunk in data.chunks(8) {
+        block.iter_mut().zip(chunk).for_each(|(b, d)| *b ^= d);
+        k1.encrypt_block(GenericArray::from_mut_slice(&mut block));
+    }
+    k2.decrypt_block(GenericArray::from_mut_slice(&mut block));
+    k1.encrypt_block(GenericArray::from_mut_slice(&mut block));
+    Some(block)
+}
+
+impl ParserResult {
+    /// Get a parsed field by its bit number.
+    pub fn field(&self, bit: u32) -> Option<&ParsedField> {
+        self.fields.iter().find(|field| field.number == bit)
+    }
+
+    /// Get a tag of the EMV data of field 55 by its hex tag, e.g. `"9F26"`, ignoring case.
+    pub fn emv_tag(&self, tag: &str) -> Option<&EmvTag> {
+        self.emv_tags.iter().find(|emv_tag| emv_tag.tag.eq_ignore_ascii_case(tag))
+    }
+
+    /// Compare two messages field by field. The MTI and bitmap differences come first, then
+    /// the fields present in only one of the messages or with different values, by bit number.
+    pub fn diff(&self, other: &ParserResult) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        if self.mti != other.mti {
+            differences.push(Difference { item: "MTI".to_string(), left: Some(self.mti.clone()), right: Some(other.mti.clone()) });
+        }
+        if self.bitmap != other.bitmap {
+            differences.push(Difference {
+                item: "Bitmap".to_string(),
+                left: Some(format!("{:?}", self.bitmap)),
+                right: Some(format!("{:?}", other.bitmap)),
+            });
+        }
+        let mut bits: Vec<u32> = self.iter().chain(other.iter()).map(|field| field.number).collect();
+        bits.sort_unstable();
+        bits.dedup();
+        for bit in bits {
+            let left = self.field(bit).map(|field| field.value.clone());
+            let right = other.field(bit).map(|field| field.value.clone());
+            if left != right {
+                differences.push(Difference { item: format!("Field {}", bit), left, right });
+            }
+        }
+        differences
+    }
+
+    /// Whether the message contains the field.
+    pub fn has_field(&self, bit: u32) -> bool {
+        self.field(bit).is_some()
+    }
+
+    /// Iterate over the parsed fields in message order.
+    pub fn iter(&self) -> core::slice::Iter<'_, ParsedField> {
+        self.fields.iter()
+    }
+
+    /// Describe the network management code of field 70 when the message is of the network
+    /// management class, such as "Echo Test" for a keep-alive.
+    pub fn network_management(&self) -> Option<&'static str> {
+        if self.decoded_mti.as_ref()?.class != "Network Management" {
+            return None;
+        }
+        describe_network_mgmt(&self.field(70)?.value)
+    }
+
+    /// The category of the response code of field 39, when the message has one.
+    pub fn response_category(&self) -> Option<ResponseCategory> {
+        Some(response_category(&response_code(&self.field(39)?.value)))
+    }
+
+    /// Check the MAC of the message against the one computed with the given key.
+    ///
+    /// Returns `None` when the MAC can not be checked: the message has no MAC, the key has the
+    /// wrong length for the algorithm, or the bytes the MAC covers are not known. Only results
+    /// returned by the parser know them; they are not serialized, so a result read back from
+    /// JSON or built by hand can not be checked.
+    pub fn verify_mac(&self, key: &[u8], algo: MacAlgo) -> Option<bool> {
+        let mac = self.mac.as_ref()?;
+        if self.mac_input.is_empty() {
+            return None;
+        }
+        match algo {
+            MacAlgo::Retail => retail_mac(key, &self.mac_input).map(|computed| computed[..] == mac[..]),
+        }
+    }
+
+    /// Format the whole report: length, header, MTI, bitmap, each field and the unparsed tail.
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let mut output = String::new();
+        if let Some(message_len) = self.message_length {
+            output.push_str(&format!("Lentgh Of Message: {}\n", message_len));
+        }
+        if let Some(header) = &self.header {
+            output.push_str(&format!("Header: {}\n", header));
+        }
+        match &self.decoded_mti {
+            Some(decoded_mti) => output.push_str(&format!("MTI: {} ({})\n", self.mti, decoded_mti)),
+            None => output.push_str(&format!("MTI: {}\n", self.mti)),
+        }
+        if let Some(network_management) = self.network_management() {
+            output.push_str(&format!("Network Management: {}\n", network_management));
+        }
+        if let Some(category) = self.response_category() {
+            output.push_str(&format!("Response Category: {}\n", category));
+        }
+        match options.bitmap_format {
+            BitmapFormat::Positions => output.push_str(&format!("First Bit Map: {:?}\n", self.bitmap)),
+            BitmapFormat::Hex => output.push_str(&format!("First Bit Map: {}\n", self.bitmap_hex)),
+            BitmapFormat::Both => output.push_str(&format!("First Bit Map: {} {:?}\n", self.bitmap_hex, self.bitmap)),
+        }
+        for field in self {
+            if options.filter.as_deref().is_some_and(|query| !field.matches(query)) {
+                continue;
+            }
+            output.push_str(&field.format(options));
+        }
+        if !self.unparsed.is_empty() {
+            output.push_str(&format!("Not parsed Part: {}\n", self.unparsed));
+        }
+        if !self.warnings.is_empty() {
+            output.push_str("Warnings:\n");
+            for warning in &self.warnings {
+                output.push_str(&format!("\t{}\n", warning));
+            }
+        }
+        output
+    }
+}
+
+/// Field 7, the transmission date and time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransmissionDateTime {
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// A parsed message with the common fields decoded into typed values.
+///
+/// The fields are decoded once by `TypedMessage::from_iso8583`, which fails when one of them
+/// is malformed. The other fields stay available through `field` and `result`.
+#[derive(Debug)]
+pub struct TypedMessage {
+    pan: Option<String>,
+    processing_code: Option<ProcessingCode>,
+    amount: Option<u64>,
+    transmission_date_time: Option<TransmissionDateTime>,
+    stan: Option<u32>,
+    rrn: Option<String>,
+    response_code: Option<String>,
+    terminal_id: Option<String>,
+    result: ParserResult,
+}
+
+impl TypedMessage {
+    /// Decode the common fields (2, 3, 4, 7, 11, 37, 39 and 41) of a parsed message.
+    pub fn from_iso8583(result: ParserResult) -> Result<TypedMessage, Iso8583Error> {
+        let value = |bit: u32| result.field(bit).map(|field| field.value.as_str());
+        let number = |bit: u32| -> Result<Option<u64>, Iso8583Error> {
+            value(bit)
+                .map(|v| v.parse::<u64>().map_err(|_| Iso8583Error::InvalidField { bit, reason: format!("{:?} is not a number", v) }))
+                .transpose()
+        };
+        let transmission_date_time = match value(7) {
+            Some(raw) => {
+                let parts = parse_date_time(7, raw, &[("month", 1..=12), ("day", 1..=31), ("hour", 0..=23), ("minute", 0..=59), ("second", 0..=59)])?;
+                let parts = parts.iter().map(|part| part.parse::<u8>()).collect::<Result<Vec<u8>, _>>()?;
+                Some(TransmissionDateTime { month: parts[0], day: parts[1], hour: parts[2], minute: parts[3], second: parts[4] })
+            }
+            None => None,
+        };
+        Ok(TypedMessage {
+            pan: value(2).map(str::to_string),
+            processing_code: value(3).map(parse_processing_code).transpose()?,
+            amount: number(4)?,
+            transmission_date_time,
+            stan: number(11)?.map(|stan| stan as u32),
+            rrn: value(37).map(str::to_string),
+            response_code: value(39).map(response_code),
+            terminal_id: value(41).map(str::to_string),
+            result,
+        })
+    }
+
+    pub fn mti(&self) -> &str {
+        &self.result.mti
+    }
+
+    /// Field 2, masked when the message was parsed with `Mode::mask_pan`.
+    pub fn pan(&self) -> Option<&str> {
+        self.pan.as_deref()
+    }
+
+    /// Field 3.
+    pub fn processing_code(&self) -> Option<&ProcessingCode> {
+        self.processing_code.as_ref()
+    }
+
+    /// Field 4, the transaction amount in minor units of its currency.
+    pub fn amount(&self) -> Option<u64> {
+        self.amount
+    }
+
+    /// Field 7.
+    pub fn transmission_date_time(&self) -> Option<TransmissionDateTime> {
+        self.transmission_date_time
+    }
+
+    /// Field 11, the system trace audit number.
+    pub fn stan(&self) -> Option<u32> {
+        self.stan
+    }
+
+    /// Field 37, the retrieval reference number.
+    pub fn rrn(&self) -> Option<&str> {
+        self.rrn.as_deref()
+    }
+
+    /// Field 39, as its 2 or 3 characters even when it was given hex encoded.
+    pub fn response_code(&self) -> Option<&str> {
+        self.response_code.as_deref()
+    }
+
+    /// Field 41, the card acceptor terminal identification.
+    pub fn terminal_id(&self) -> Option<&str> {
+        self.terminal_id.as_deref()
+    }
+
+    /// Get any parsed field by its bit number.
+    pub fn field(&self, bit: u32) -> Option<&ParsedField> {
+        self.result.field(bit)
+    }
+
+    /// The parse result the message was built from.
+    pub fn result(&self) -> &ParserResult {
+        &self.result
+    }
+
+    pub fn into_result(self) -> ParserResult {
+        self.result
+    }
+}
+
+impl TryFrom<ParserResult> for TypedMessage {
+    type Error = Iso8583Error;
+
+    fn try_from(result: ParserResult) -> Result<Self, Self::Error> {
+        TypedMessage::from_iso8583(result)
+    }
+}
+
+/// Normalize a hex string as pasted from logs or traces into uppercase hex digits.
+///
+/// Quotes, spaces, tabs, newlines, colons and `0x` prefixes are removed. Any other non hex
+/// character is reported with its index in `input`, as is an odd number of digits.
+pub fn normalize_hex(input: &str) -> Result<String, Iso8583Error> {
+    let mut hex = String::with_capacity(input.len());
+    let mut chars = input.chars().enumerate().peekable();
+    let mut token_start = true;
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' | ' ' | '\t' | '\r' | '\n' | ':' => {
+                token_start = true;
+                continue;
+            }
+            '0' if token_start && matches!(chars.peek(), Some((_, 'x' | 'X'))) => {
+                chars.next();
+            }
+            c if c.is_ascii_hexdigit() => hex.push(c.to_ascii_uppercase()),
+            c => return Err(hex::FromHexError::InvalidHexCharacter { c, index }.into()),
+        }
+        token_start = false;
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(hex::FromHexError::OddLength.into());
+    }
+    Ok(hex)
+}
+
+/// How a message given to the parser is encoded, e.g. by the tool that captured it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputFormat {
+    /// Hex digits, cleaned up by `normalize_hex`.
+    #[default]
+    Hex,
+    /// Standard base64, with or without its padding. Whitespace is ignored.
+    Base64,
+    /// The bytes of the message themselves.
+    Raw,
+}
+
+impl InputFormat {
+    /// Decode `input` into the bytes of the message, to be parsed by `parse_iso8583_bytes`.
+    pub fn decode(self, input: &[u8]) -> Result<Vec<u8>, Iso8583Error> {
+        match self {
+            InputFormat::Hex => Ok(hex::decode(normalize_hex(&String::from_utf8_lossy(input))?)?),
+            InputFormat::Base64 => decode_base64(input),
+            InputFormat::Raw => Ok(input.to_vec()),
+        }
+    }
+}
+
+fn decode_base64(input: &[u8]) -> Result<Vec<u8>, Iso8583Error> {
+    let mut bytes = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    let mut digits = 0;
+    let mut padding = 0;
+    for (index, &c) in input.iter().enumerate() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            c if c.is_ascii_whitespace() => continue,
+            c => return Err(Iso8583Error::InvalidBase64(format!("invalid character {:?} at index {}", c as char, index))),
+        };
+        if padding > 0 {
+            return Err(Iso8583Error::InvalidBase64(format!("data follows the padding at index {}", index)));
+        }
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        digits += 1;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // A single character of a last group of 4 does not hold a whole byte
+    if digits % 4 == 1 {
+        return Err(Iso8583Error::InvalidBase64("the input ends with a lone character".to_string()));
+    }
+    if padding > 0 && (digits + padding) % 4 != 0 {
+        return Err(Iso8583Error::InvalidBase64(format!("{} padding characters after {} characters", padding, digits)));
+    }
+    Ok(bytes)
+}
+
+/// Prepend a 2-byte binary length header (MLI) to a message given in hex string format,
+/// e.g. `0022` for a message of 34 bytes. The message is cleaned up by `normalize_hex`.
+pub fn with_length_header(message_hex: &str) -> Result<String, Iso8583Error> {
+    let hex = normalize_hex(message_hex)?;
+    let length = u16::try_from(hex.len() / 2).map_err(|_| Iso8583Error::MessageTooLong(hex.len() / 2))?;
+    Ok(format!("{:04X}{}", length, hex))
+}
+
+/// Parse an ISO8583 message given in hex string format.
+///
+/// The message is first cleaned up by `normalize_hex`, then decoded and handed to
+/// `parse_iso8583_bytes`.
+pub fn parse_iso8583(message: &str, including_header_length: bool, mode: &Mode, catalog: Option<&FieldCatalog>) -> Result<ParserResult, Iso8583Error> {
+    let data = hex::decode(normalize_hex(message)?)?;
+    parse_iso8583_bytes(&data, including_header_length, mode, catalog)
+}
+
+/// Parse an ISO8583 message that may or may not start with a length header and a header.
+///
+/// The message is first parsed with them. When that fails or the MTI is not made of 4
+/// digits, it is parsed again without them, falling back to the first interpretation if this
+/// fails too. The returned flag tells whether the message was parsed with its length header.
+pub fn parse_iso8583_auto(message: &str, mode: &Mode, catalog: Option<&FieldCatalog>) -> Result<(ParserResult, bool), Iso8583Error> {
+    let framed = parse_iso8583(message, true, mode, catalog);
+    if let Ok(result) = framed {
+        if result.decoded_mti.is_some() {
+            return Ok((result, true));
+        }
+        return Ok(parse_iso8583(message, false, mode, catalog).map(|result| (result, false)).unwrap_or((result, true)));
+    }
+    match (parse_iso8583(message, false, mode, catalog), framed) {
+        (Ok(result), _) => Ok((result, false)),
+        // A length header not matching the message means it has none
+        (Err(e), Err(Iso8583Error::LengthMismatch { .. } | Iso8583Error::InvalidLengthHeader(_) | Iso8583Error::HeaderTooLong { .. })) => Err(e),
+        (Err(_), framed) => framed.map(|result| (result, true)),
+    }
+}
+
+/// Reads a message in hex string format from front to back.
+///
+/// Unlike `StringManipulation::get_slice_until`, the message is left untouched: reading only
+/// moves a position forward, so what was read stays available and offsets are positions.
+///
+/// ```
+/// use iso8583_parser::Cursor;
+///
+/// let mut cursor = Cursor::new("01002000000000000000930000");
+/// assert_eq!(cursor.take(4).unwrap(), "0100");
+/// assert_eq!(cursor.peek(4).unwrap(), "2000");
+/// assert_eq!(cursor.position(), 4);
+/// assert_eq!(cursor.remaining(), 22);
+/// assert!(cursor.take(50).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    data: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(data: &'a str) -> Self {
+        Cursor { data, pos: 0 }
+    }
+
+    /// Read the next `length` characters. Fails with `Iso8583Error::TruncatedField` when
+    /// fewer remain, like `get_slice_until`.
+    pub fn take(&mut self, length: usize) -> Result<&'a str, Iso8583Error> {
+        let slice = self.peek(length)?;
+        self.pos += length;
+        Ok(slice)
+    }
+
+    /// Look at the next `length` characters without reading them.
+    pub fn peek(&self, length: usize) -> Result<&'a str, Iso8583Error> {
+        let rest = self.rest();
+        if length > rest.len() {
+            return Err(Iso8583Error::TruncatedField { bit: 0, needed: length, available: rest.len() });
+        }
+        Ok(&rest[..length])
+    }
+
+    /// Number of characters read so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Number of characters left to read.
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// The characters left to read.
+    pub fn rest(&self) -> &'a str {
+        &self.data[self.pos..]
+    }
+
+    /// The whole message, including what was already read.
+    pub fn data(&self) -> &'a str {
+        self.data
+    }
+}
+
+/// Read the bitmaps at the cursor and return the numbers of the fields present, without the
+/// bits announcing the secondary and tertiary bitmaps.
+fn read_bitmap(cursor: &mut Cursor, warnings: &mut Vec<String>) -> Result<Vec<u32>, Iso8583Error> {
+    // The first bit of each bitmap tells whether another one follows, up to a tertiary bitmap
+    let mut bitmap_bytes = hex::decode(cursor.take(16)?)?;
+    for (bit, name) in [(1, "secondary"), (65, "tertiary")] {
+        if bitmap_bytes[bitmap_bytes.len() - 8] & 0x80 == 0 {
+            break;
+        }
+        let next = hex::decode(cursor.take(16)?)?;
+        if next.iter().all(|&b| b == 0) {
+            warnings.push(format!("Bit {} is set but the {} bitmap is empty", bit, name));
+        }
+        bitmap_bytes.extend(next);
+    }
+    let mut bitmap = positions_of_set_bits_bytes(&bitmap_bytes);
+    if bitmap_bytes.len() > 8 {
+        bitmap.retain(|&x| x != 1 && x != 65);
+    }
+    Ok(bitmap)
+}
+
+/// Read only the bitmaps of a message given in hex string format and return the numbers of
+/// the fields present, e.g. to route it. The fields are not parsed, so fields missing from
+/// the catalog are listed too.
+///
+/// With `including_header_length`, the length header and header of `Mode::default()` are
+/// skipped without checking the length.
+pub fn parse_bitmap(message: &str, including_header_length: bool) -> Result<Vec<u32>, Iso8583Error> {
+    let hex = normalize_hex(message)?;
+    let mut cursor = Cursor::new(&hex);
+    if including_header_length {
+        let mode = Mode::default();
+        cursor.take(mode.length_header.width() * 2 + mode.header_len)?;
+    }
+    cursor.take(4)?;
+    read_bitmap(&mut cursor, &mut Vec::new())
+}
+
+/// Parse an ISO8583 message given as raw bytes, e.g. as read from a socket.
+///
+/// Fields are looked up in `catalog`, or when `None` is given, in the catalog matching the
+/// version of the MTI as chosen by `FieldCatalog::for_mti`.
+pub fn parse_iso8583_bytes(data: &[u8], including_header_length: bool, mode: &Mode, catalog: Option<&FieldCatalog>) -> Result<ParserResult, Iso8583Error> {
+    let mut warnings = Vec::new();
+    let mut trailer_ok = None;
+    // The message, and the LRC to check with the bytes it covers
+    let (data, lrc) = if mode.strip_framing && data.first() == Some(&STX) {
+        strip_framing(data, mode.lrc)?
+    } else {
+        match data.split_last() {
+            Some((&lrc, body)) if mode.lrc => (body, Some((lrc, body))),
+            _ => (data, None),
+        }
+    };
+    if let Some((lrc, covered)) = lrc {
+        let computed = compute_lrc(covered);
+        if computed != lrc {
+            warnings.push(format!("LRC {:02X} does not match the computed {:02X}", lrc, computed));
+        }
+        trailer_ok = Some(computed == lrc);
+    }
+    let hex = hex::encode_upper(data);
+    let mut cursor = Cursor::new(&hex);
+    let mut message_length = None;
+    let mut header = None;
+    // What follows the declared length of the message, left unparsed
+    let mut trailer = "";
+    if including_header_length {
+        let message_len = mode.length_header.read(data)? as u32 * 2;
+        cursor.take(mode.length_header.width() * 2)?;
+        let mismatch = match mode.length_check {
+            LengthCheck::Exact => cursor.remaining() != message_len as usize,
+            LengthCheck::AtLeast => cursor.remaining() < message_len as usize,
+            LengthCheck::Ignore => false,
+        };
+        if mismatch {
+            return Err(Iso8583Error::LengthMismatch { expected: message_len as usize, actual: cursor.remaining() });
+        }
+        if mode.length_check == LengthCheck::AtLeast {
+            let end = cursor.position() + message_len as usize;
+            trailer = &hex[end..];
+            cursor = Cursor::new(&hex[..end]);
+            cursor.take(mode.length_header.width() * 2)?;
+        }
+        message_length = Some(message_len);
+        if mode.header_len > cursor.remaining() {
+            return Err(Iso8583Error::HeaderTooLong { header_len: mode.header_len, available: cursor.remaining() });
+        }
+        header = Some(cursor.take(mode.header_len)?.to_string());
+    }
+    let body_start = cursor.position() / 2;
+    let mti = cursor.take(4)?.to_string();
+    let decoded_mti = match decode_mti(&mti) {
+        Ok(decoded_mti) => Some(decoded_mti),
+        Err(_) if mode.lenient => {
+            warnings.push(format!("MTI {:?} is not made of 4 digits", mti));
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    let version_catalog;
+    let catalog = match catalog {
+        Some(c) => c,
+        None => {
+            version_catalog = FieldCatalog::for_mti(&mti);
+            &version_catalog
+        }
+    };
+    let bitmap = read_bitmap(&mut cursor, &mut warnings)?;
+
+    let mut fields = Vec::new();
+    let mut mac = None;
+    let mut mac_input = Vec::new();
+    let mut emv_tags = Vec::new();
+    for &bit in &bitmap {
+        let Some(spec) = catalog.get(bit) else {
+            if !mode.lenient {
+                return Err(Iso8583Error::UnimplementedField(bit));
+            }
+            // Without a definition the length of the field is unknown, so the rest stays unparsed
+            warnings.push(format!("Field {}: field is not implemented", bit));
+            fields.push(ParsedField {
+                number: bit,
+                name: "(unimplemented, raw tail)".to_string(),
+                length: cursor.remaining() as u32,
+                value: cursor.rest().to_string(),
+                raw: None,
+                details: Vec::new(),
+                start_offset: cursor.position(),
+                end_offset: cursor.data().len(),
+                warnings: vec!["field is not implemented".to_string()],
+                sub_elements: None,
+            });
+            break;
+        };
+        if matches!(bit, 64 | 128) {
+            mac_input = data[body_start..cursor.position() / 2].to_vec();
+        }
+        let ascii_spec;
+        let spec = if mode.numeric_encoding == NumericEncoding::Ascii && spec.encoding == Encoding::Bcd {
+            ascii_spec = FieldSpec { encoding: Encoding::Ascii, ..spec.clone() };
+            &ascii_spec
+        } else {
+            spec
+        };
+        let start_offset = cursor.position();
+        let length = spec.read_length(&mut cursor, mode.length_encoding).map_err(|e| e.in_field(bit))?;
+        let field_value = cursor.take(spec.wire_length(length) as usize).map_err(|e| e.in_field(bit))?;
+        let mut field = decode_field(bit, length, field_value.to_string(), spec, mode)?;
+        field.start_offset = start_offset;
+        field.end_offset = cursor.position();
+        if let Some(name) = mode.field_names.get(&bit) {
+            field.name = name.clone();
+        }
+        if matches!(bit, 64 | 128) {
+            if field.value.len() != 16 {
+                return Err(Iso8583Error::InvalidField {
+                    bit,
+                    reason: format!("MAC must be 8 bytes but is {} characters long", field.value.len()),
+                });
+            }
+            mac = Some(hex::decode(&field.value)?);
+        }
+        if let Some(SubElements::Emv(tags)) = field.sub_elements.take_if(|elements| matches!(elements, SubElements::Emv(_))) {
+            // Invalid EMV data is already reported in the details of the field
+            emv_tags = tags;
+        }
+        warnings.extend(field.warnings.iter().map(|warning| format!("Field {}: {}", bit, warning)));
+        fields.push(field);
+    }
+
+    // Lenient parsing stops at the first unimplemented field, leaving the following ones out
+    let missing: Vec<String> = bitmap.iter().filter(|&&bit| !fields.iter().any(|f| f.number == bit)).map(|bit| bit.to_string()).collect();
+    if !missing.is_empty() {
+        warnings.push(format!("Fields signalled by the bitmap but not parsed: {}", missing.join(", ")));
+    }
+
+    // Amounts are formatted here as their currency fields come after them
+    for (amount_bit, currency_bit) in [(4, 49), (5, 50), (6, 51)] {
+        let currency = fields.iter().find(|f| f.number == currency_bit).map(|f| f.value.clone());
+        if let Some(field) = fields.iter_mut().find(|f| f.number == amount_bit) {
+            let amount = format_amount(&field.value, currency.as_deref());
+            match currency {
+                Some(currency) => field.details.push(format!("\tAmount: {} ({})", amount, currency)),
+                None => field.details.push(format!("\tAmount: {}", amount)),
+            }
+        }
+    }
+
+    if decoded_mti.as_ref().is_some_and(|decoded_mti| decoded_mti.class == "Network Management") {
+        if let Some(field) = fields.iter_mut().find(|f| f.number == 70) {
+            match describe_network_mgmt(&field.value) {
+                Some(description) => field.details.push(format!("\tNetwork Management Code {}: {}", field.value, description)),
+                None => field.details.push(format!("\tNetwork Management Code {}: (unknown code)", field.value)),
+            }
+        }
+    }
+
+    if cursor.remaining() > 0 {
+        warnings.push(format!("{} characters were left unparsed", cursor.remaining()));
+    }
+    if !trailer.is_empty() {
+        warnings.push(format!("{} characters follow the length declared by the length header", trailer.len()));
+    }
+
+    let unparsed = format!("{}{}", cursor.rest(), trailer);
+    let bitmap_hex = bits_to_bitmap_hex(&bitmap);
+    Ok(ParserResult { message_length, header, mti, decoded_mti, bitmap, bitmap_hex, fields, unparsed, mac, warnings, emv_tags, trailer_ok, mac_input })
+}
+
+/// Parse several concatenated ISO8583 messages given in hex string format.
+///
+/// When `including_header_length` is true, the length header of each message is used to
+/// split the stream and one result is returned per message. Parsing stops when fewer bytes
+/// than a length header remain. Otherwise, or when `Mode::length_check` allows the declared
+/// lengths to be wrong, the whole input is parsed as a single message.
+pub fn parse_iso8583_stream(data: &str, including_header_length: bool, mode: &Mode, catalog: Option<&FieldCatalog>) -> Vec<Result<ParserResult, Iso8583Error>> {
+    if !including_header_length || mode.length_check != LengthCheck::Exact {
+        return vec![parse_iso8583(data, including_header_length, mode, catalog)];
+    }
+    let data = match normalize_hex(data).and_then(|hex| Ok(hex::decode(hex)?)) {
+        Ok(data) => data,
+        Err(e) => return vec![Err(e)],
+    };
+    let mut results = Vec::new();
+    let mut rest = &data[..];
+    while rest.len() >= mode.length_header.width() {
+        let message_len = match mode.length_header.read(rest) {
+            Ok(length) => mode.length_header.width() + length + usize::from(mode.lrc),
+            Err(e) => {
+                results.push(Err(e));
+                break;
+            }
+        };
+        if message_len > rest.len() {
+            results.push(Err(Iso8583Error::LengthMismatch { expected: message_len * 2, actual: rest.len() * 2 }));
+            break;
+        }
+        let (message, remaining) = rest.split_at(message_len);
+        results.push(parse_iso8583_bytes(message, true, mode, catalog));
+        rest = remaining;
+    }
+    results
+}
+
+/// An incremental parser for messages arriving in chunks, such as off a socket.
+///
+/// Each message starts with a length header read as `Mode::length_header` and is parsed
+/// like `parse_iso8583_bytes` with `including_header_length`, so `Mode::header_len` should
+/// be 0 when the messages have no header. Bytes of an incomplete message, or of a partial
+/// length header, are kept until the following chunks complete it.
+#[derive(Debug, Clone)]
+pub struct StreamParser {
+    buffer: Vec<u8>,
+    mode: Mode,
+    catalog: Option<FieldCatalog>,
+}
+
+impl StreamParser {
+    /// Create a parser with nothing buffered, parsing with `catalog` or the catalog of each MTI.
+    pub fn new(mode: Mode, catalog: Option<FieldCatalog>) -> Self {
+        StreamParser { buffer: Vec::new(), mode, catalog }
+    }
+
+    /// Add received bytes and parse the messages they complete.
+    ///
+    /// An invalid length header leaves no way to find where the next message starts, so it
+    /// is reported as an error and the buffered bytes are dropped.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Result<ParserResult, Iso8583Error>> {
+        self.buffer.extend_from_slice(data);
+        let width = self.mode.length_header.width();
+        let mut results = Vec::new();
+        let mut consumed = 0;
+        while self.buffer.len() - consumed >= width {
+            let rest = &self.buffer[consumed..];
+            let message_len = match self.mode.length_header.read(rest) {
+                Ok(length) => width + length + usize::from(self.mode.lrc),
+                Err(e) => {
+                    results.push(Err(e));
+                    consumed = self.buffer.len();
+                    break;
+                }
+            };
+            if message_len > rest.len() {
+                break;
+            }
+            results.push(parse_iso8583_bytes(&rest[..message_len], true, &self.mode, self.catalog.as_ref()));
+            consumed += message_len;
+        }
+        self.buffer.drain(..consumed);
+        results
+    }
+
+    /// Number of bytes received but not yet parsed, waiting for the rest of their message.
+    pub fn pending_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// Count the messages of a batch by MTI and response code and add up their amounts.
+pub fn summarize(results: &[ParserResult]) -> BatchSummary {
+    let mut summary = BatchSummary { messages: results.len(), ..Default::default() };
+    for result in results {
+        *summary.by_mti.entry(result.mti.clone()).or_default() += 1;
+        if let Some(field) = result.field(39) {
+            *summary.by_response_code.entry(response_code(&field.value)).or_default() += 1;
+        }
+        if let Some(amount) = result.field(4).and_then(|field| field.value.parse::<u64>().ok()) {
+            summary.amounts += 1;
+            summary.total_amount += amount;
+        }
+    }
+    if summary.amounts > 0 {
+        summary.average_amount = Some(summary.total_amount / summary.amounts as u64);
+    }
+    summary
+}
+
+/// Parse an ISO8583 message and return the result as pretty printed JSON.
+#[cfg(feature = "serde")]
+pub fn parse_iso8583_json(message: &str, including_header_length: bool, mode: &Mode, catalog: Option<&FieldCatalog>) -> Result<String, Iso8583Error> {
+    let result = parse_iso8583(message, including_header_length, mode, catalog)?;
+    serde_json::to_string_pretty(&result).map_err(|e| Iso8583Error::Serialization(e.to_string()))
+}
+
+/// Builder of ISO8583 messages in hex string format, the inverse of `parse_iso8583`.
+///
+/// Field values are given the way `parse_iso8583` reports them, so ASCII fields are plain text
+/// and all other fields are hex strings.
+///
+/// ```
+/// use iso8583_parser::{parse_iso8583, Iso8583Builder, Mode};
+///
+/// let message = Iso8583Builder::new()
+///     .set_mti("0100")
+///     .set_field(3, "930000")
+///     .build()
+///     .unwrap();
+/// assert_eq!(message, "01002000000000000000930000");
+///
+/// let mode = Mode::default();
+/// let result = parse_iso8583(&message, false, &mode, None).unwrap();
+/// assert_eq!(result.fields[0].value, "930000");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Iso8583Builder {
+    catalog: Option<FieldCatalog>,
+    mti: String,
+    fields: BTreeMap<u32, String>,
+}
+
+impl Iso8583Builder {
+    /// Create a builder using the catalog matching the version of the MTI, see `FieldCatalog::for_mti`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder using the given field definitions.
+    pub fn with_catalog(catalog: FieldCatalog) -> Self {
+        Iso8583Builder { catalog: Some(catalog), ..Self::default() }
+    }
+
+    /// Set the message type indicator.
+    pub fn set_mti(&mut self, mti: &str) -> &mut Self {
+        self.mti = mti.to_string();
+        self
+    }
+
+    /// Set the value of a field, replacing any previous value.
+    pub fn set_field(&mut self, bit: u32, value: &str) -> &mut Self {
+        self.fields.insert(bit, value.to_string());
+        self
+    }
+
+    /// Build the message, computing the bitmaps and the length prefixes.
+    pub fn build(&self) -> Result<String, Iso8583Error> {
+        if self.mti.len() != 4 {
+            return Err(Iso8583Error::InvalidMti(self.mti.clone()));
+        }
+        let version_catalog;
+        let catalog = match &self.catalog {
+            Some(c) => c,
+            None => {
+                version_catalog = FieldCatalog::for_mti(&self.mti);
+                &version_catalog
+            }
+        };
+        let mut data = String::new();
+        for (&bit, value) in &self.fields {
+            if !matches!(bit, 2..=64 | 66..=128 | 130..=192) {
+                return Err(Iso8583Error::InvalidField { bit, reason: "field can not be set".to_string() });
+            }
+            let spec = catalog.get(bit).ok_or(Iso8583Error::UnimplementedField(bit))?;
+            data.push_str(&spec.encode(bit, value)?);
+        }
+
+        let bits: Vec<u32> = self.fields.keys().copied().collect();
+        Ok(format!("{}{}{}", self.mti, bits_to_bitmap_hex(&bits), data))
+    }
+}
 
-#[derive(Debug)]
-pub struct  LTV {
-    pub length: usize,
-    pub tag: u8,
-    pub value: String,
+/// Returns the positions of set bits in a binary number.
+pub fn positions_of_set_bits(n: u64) -> Vec<u32> {
+    positions_of_set_bits_bytes(&n.to_be_bytes())
 }
-pub struct  PrivateTlv {
-    pub tag: String,
-    pub length: usize,
-    pub value: String,
+
+/// Returns the 1-based positions of set bits across a bitmap of any length, starting with the
+/// most significant bit of the first byte.
+pub fn positions_of_set_bits_bytes(bitmap: &[u8]) -> Vec<u32> {
+    (0..bitmap.len() as u32 * 8)
+        .filter(|&bit| bitmap[bit as usize / 8] & (0x80 >> (bit % 8)) != 0)
+        .map(|bit| bit + 1)
+        .collect()
 }
 
-pub struct Mode {
-    pub enabled_private_tlv: bool,
-    pub enabled_private_ltv: bool,
+/// Build the bitmaps in hex for the given field positions, the inverse of `positions_of_set_bits`.
+///
+/// A secondary bitmap is added, and bit 1 set, when a position is above 64, and likewise a
+/// tertiary bitmap and bit 65 for positions above 128. Positions 0 and above 192 are ignored.
+pub fn bits_to_bitmap_hex(positions: &[u32]) -> String {
+    let highest = positions.iter().copied().filter(|&p| p <= 192).max().unwrap_or(0);
+    let bitmap_count = highest.saturating_sub(1) as usize / 64 + 1;
+    let mut bitmaps = vec![0u64; bitmap_count];
+    for &position in positions.iter().filter(|&&p| (1..=192).contains(&p)) {
+        bitmaps[(position - 1) as usize / 64] |= 1 << (63 - (position - 1) % 64);
+    }
+    bitmaps.iter_mut().take(bitmap_count - 1).for_each(|bitmap| *bitmap |= 1 << 63);
+    bitmaps.iter().map(|bitmap| format!("{:016X}", bitmap)).collect()
 }
 
-/// Returns the positions of set bits in a binary number.
-pub fn positions_of_set_bits(n: u64) -> Vec<u32> {
-    (0..64).filter(|&bit| 1 & (n >> (63 - bit)) != 0).map(|bit| bit + 1).collect()
+/// Remove the pad nibble of a packed BCD value with an odd number of digits, where
+/// `FieldSpec::encode` puts it: first for fixed length fields, last for variable ones.
+fn strip_bcd_padding(value: &str, spec: &FieldSpec) -> String {
+    match spec.length_type {
+        LengthType::Fixed => value[1..].to_string(),
+        LengthType::LLVar | LengthType::LLLVar => value[..value.len() - 1].to_string(),
+    }
+}
+
+/// Parse the EMV data of field 55, given in hex, into its tags.
+#[cfg(feature = "std")]
+pub fn parse_emv_tags(value: &str) -> Result<Vec<EmvTag>, Iso8583Error> {
+    let tags = parse_tlv_vec(&hex::decode(value)?)
+        .map_err(|e| Iso8583Error::InvalidField { bit: 55, reason: e.to_string() })?;
+    Ok(tags
+        .into_iter()
+        .map(|tag| EmvTag {
+            tag: hex::encode_upper(&tag.id),
+            name: tag.name.to_string(),
+            length: tag.length,
+            value: tag.value,
+            nest_level: tag.nest_level,
+        })
+        .collect())
+}
+
+/// EMV decoding needs the standard library, so no tags are found without it.
+#[cfg(not(feature = "std"))]
+pub fn parse_emv_tags(_value: &str) -> Result<Vec<EmvTag>, Iso8583Error> {
+    Ok(Vec::new())
+}
+
+/// Write the tags and values of private TLV elements that are kept in hex in `hex_case`.
+fn private_tlv_hex_case(tlvs: Vec<PrivateTlv>, format: &PrivateTlvFormat, hex_case: HexCase) -> Vec<PrivateTlv> {
+    let is_hex = |encoding| matches!(encoding, Encoding::Bcd | Encoding::Binary);
+    tlvs.into_iter()
+        .map(|tlv| PrivateTlv {
+            tag: if is_hex(format.tag_encoding) { hex_case.apply(&tlv.tag) } else { tlv.tag },
+            value: if is_hex(format.value_encoding) { hex_case.apply(&tlv.value) } else { tlv.value },
+            ..tlv
+        })
+        .collect()
+}
+
+/// Decode the value of a field, sliced from the message with its padding, and describe it.
+fn decode_field(field_number: u32, length: u32, mut field_value: String, spec: &FieldSpec, mode: &Mode) -> Result<ParsedField, Iso8583Error> {
+    if field_value.len() > length as usize {
+        field_value = strip_bcd_padding(&field_value, spec);
+    }
+    // Fields processed on their own may be given in lowercase
+    field_value = field_value.canonical_hex();
+
+    let mut value = match spec.encoding {
+        Encoding::Ascii => field_value.hex_to_ascii(),
+        Encoding::Ebcdic => field_value.hex_to_ebcdic(),
+        Encoding::Bcd | Encoding::Binary => Ok(field_value.to_string()),
+    }.map_err(|e| Iso8583Error::InvalidField { bit: field_number, reason: e.to_string() })?;
+
+    // Keeping the raw form of a masked PAN would defeat the masking
+    let mut raw = match spec.encoding {
+        Encoding::Ascii | Encoding::Ebcdic if !(mode.mask_pan && matches!(field_number, 2 | 35 | 45)) => Some(field_value.clone()),
+        _ => None,
+    };
+
+    let mut details = Vec::new();
+    let mut warnings = Vec::new();
+    let mut sub_elements = None;
+    if field_number == 2 {
+        details.push(format!("\t{}", luhn_status(&value)));
+        if !luhn_is_valid(&value) {
+            warnings.push("PAN fails the Luhn check".to_string());
+        }
+        if mode.mask_pan {
+            value = mask_pan(&value);
+        }
+    }
+    else if field_number == 3 {
+        match parse_processing_code(&value) {
+            Ok(processing_code) => details.push(processing_code.to_string()),
+            Err(e) => details.push(format!("Error parsing processing code: {}", e)),
+        }
+    }
+    else if matches!(field_number, 7 | 12 | 13) {
+        let formatted = match field_number {
+            7 => format_field7(&value),
+            12 => format_field12(&value),
+            _ => format_field13(&value),
+        };
+        match formatted {
+            Ok(formatted) => details.push(format!("\t{}", formatted)),
+            Err(e) => details.push(format!("Error parsing date/time: {}", e)),
+        }
+    }
+    else if field_number == 22 {
+        match parse_pos_entry_mode(&value) {
+            Ok(pos_entry_mode) => details.push(pos_entry_mode.to_string()),
+            Err(e) => details.push(format!("Error parsing POS entry mode: {}", e)),
+        }
+    }
+    else if field_number == 23 {
+        // Usually sent zero padded to 3 digits, e.g. 001 for the first card issued
+        if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+            let number = value.trim_start_matches('0');
+            details.push(format!("\tCard Sequence Number: {}", if number.is_empty() { "0" } else { number }));
+        } else {
+            warnings.push(format!("Card sequence number {} is not numeric", value));
+        }
+    }
+    else if field_number == 35 {
+        match parse_track2(&value) {
+            Ok(mut track2) => {
+                let luhn = luhn_status(&track2.pan);
+                if !luhn_is_valid(&track2.pan) {
+                    warnings.push("PAN fails the Luhn check".to_string());
+                }
+                if mode.mask_pan {
+                    track2.pan = mask_pan(&track2.pan);
+                }
+                details.push(format!("{} {}", track2, luhn));
+            }
+            Err(e) => details.push(format!("Error parsing Track 2: {}", e)),
+        }
+        if mode.mask_pan {
+            value = mask_track2(&value);
+        }
+    }
+    else if matches!(field_number, 102 | 103) {
+        // Only account numbers that look like a PAN carry a Luhn check digit
+        if (12..=19).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit()) {
+            details.push(format!("\t{}", luhn_status(&value)));
+            if !luhn_is_valid(&value) {
+                warnings.push("Account number fails the Luhn check".to_string());
+            }
+        }
+    }
+    else if field_number == 90 {
+        match parse_original_data_elements(&value) {
+            Ok(original) => details.push(original.to_string()),
+            Err(e) => details.push(format!("Error parsing original data elements: {}", e)),
+        }
+    }
+    else if field_number == 43 {
+        // A custom catalog may declare the field as binary, in which case it holds hex encoded characters
+        let text = match spec.encoding {
+            Encoding::Binary => value.clone().hex_to_ascii().unwrap_or_else(|_| value.clone()),
+            _ => value.clone(),
+        };
+        match parse_card_acceptor_location(&text) {
+            Ok(location) => details.push(location.to_string()),
+            Err(e) => details.push(format!("Error parsing card acceptor location: {}", e)),
+        }
+    }
+    else if field_number == 45 {
+        if mode.mask_pan {
+            value = mask_track1(&value);
+        }
+    }
+    else if field_number == 39 {
+        let code = response_code(&value);
+        match describe_response_code(&code) {
+            Some(description) => details.push(format!("\tResponse Code {}: {}", code, description)),
+            None => {
+                details.push(format!("\tResponse Code {}: (unknown response code)", code));
+                warnings.push(format!("unknown response code {}", code));
+            }
+        }
+    }
+    else if matches!(field_number, 49..=51) {
+        if currency_alpha_code(&value).is_none() && !CURRENCIES.iter().any(|(_, alpha)| *alpha == value) {
+            warnings.push(format!("unknown currency code {}", value));
+        }
+    }
+    else if field_number == 54 {
+        let amounts = value.clone().hex_to_ascii().map_err(Iso8583Error::from).and_then(|v| format_additional_amounts(&v));
+        match amounts {
+            Ok(amounts) => details.extend(amounts),
+            Err(e) => details.push(format!("Error parsing additional amounts: {}", e)),
+        }
+    }
+    else if field_number == 52 {
+        // The PIN block is encrypted, so its bytes are shown as they are
+        let hex = mode.hex_case.apply(&value);
+        let bytes: Vec<&str> = (0..hex.len()).step_by(2).map(|i| &hex[i..(i + 2).min(hex.len())]).collect();
+        details.push(format!("\tBytes: {}", bytes.join(" ")));
+    }
+    else if field_number == 55 {
+        let tags = parse_emv_tags(&value).map_err(|e| match e {
+            Iso8583Error::InvalidField { reason, .. } => reason,
+            e => e.to_string(),
+        });
+        match tags {
+            Ok(tags) => {
+                details.extend(tags.iter().map(|tag| tag.detail(mode.hex_case)));
+                if let Some(tvr) = tags.iter().find(|tag| tag.tag == "95") {
+                    details.extend(decode_tvr(&tvr.value).iter().map(|flag| format!("\tTVR: {}", flag)));
+                }
+                sub_elements = Some(SubElements::Emv(tags));
+            }
+            Err(e) if mode.tlv_error_policy == TlvErrorPolicy::Fail => {
+                return Err(Iso8583Error::InvalidField { bit: field_number, reason: format!("invalid EMV data: {}", e) });
+            }
+            Err(e) => {
+                details.push(format!("Error parsing TLV: {}", e));
+                if mode.tlv_error_policy == TlvErrorPolicy::Raw {
+                    details.push(format!("\tRaw: {}", mode.hex_case.apply(&value)));
+                }
+            }
+        }
+    }
+    else if field_number == 48 || field_number == 121 {
+        if mode.enabled_private_tlv {
+            let mut tlv_private_value = value.clone();
+            match tlv_private_value.parse_private_tlv_with(&mode.private_tlv_format) {
+                Ok(tlvs_p) => {
+                    let tlvs_p = private_tlv_hex_case(tlvs_p, &mode.private_tlv_format, mode.hex_case);
+                    tlvs_p.iter().for_each(|tlv_p| details.push(tlv_p.to_string()));
+                    if !tlv_private_value.is_empty() {
+                        details.push(format!("\tPadding: {}", mode.hex_case.apply(&tlv_private_value)));
+                    }
+                    sub_elements = Some(SubElements::PrivateTlv(tlvs_p));
+                }
+                Err(e) => details.push(format!("Error parsing private tlv: {}", e)),
+            }
+        }
+        else if mode.enabled_private_ltv {
+            let mut ltv_value = value.clone();
+            match ltv_value.parse_private_ltv() {
+                Ok(ltvs) => {
+                    let ltvs: Vec<LTV> = ltvs.into_iter().map(|ltv| LTV { value: mode.hex_case.apply(&ltv.value), ..ltv }).collect();
+                    ltvs.iter().for_each(|ltv| details.push(ltv.to_string()));
+                    sub_elements = Some(SubElements::Ltv(ltvs));
+                }
+                Err(e) => details.push(format!("Error parsing LTV: {}", e)),
+            }
+        }
+        else if mode.auto_detect_private {
+            if let Ok(tlvs_p) = value.clone().parse_private_tlv_with(&mode.private_tlv_format) {
+                let tlvs_p = private_tlv_hex_case(tlvs_p, &mode.private_tlv_format, mode.hex_case);
+                tlvs_p.iter().for_each(|tlv_p| details.push(tlv_p.to_string()));
+                sub_elements = Some(SubElements::PrivateTlv(tlvs_p));
+            }
+        }
+    }
+    if matches!(spec.encoding, Encoding::Bcd | Encoding::Binary) {
+        value = mode.hex_case.apply(&value);
+    }
+    raw = raw.map(|raw| mode.hex_case.apply(&raw));
+    Ok(ParsedField { number: field_number, name: spec.name.clone(), length, value, raw, details, start_offset: 0, end_offset: 0, warnings, sub_elements })
 }
 
 /// Trait for string manipulation operations.
 pub trait StringManipulation {
     /// Get a slice of the string until a specified length.
-    fn get_slice_until(&mut self, length: usize) -> String;
+    ///
+    /// Fails with `Iso8583Error::TruncatedField` when fewer characters remain; the error
+    /// refers to field 0 unless it is raised while processing a field.
+    fn get_slice_until(&mut self, length: usize) -> Result<String, Iso8583Error>;
 
     /// Convert a hex string to ASCII.
     fn hex_to_ascii(&mut self) -> Result<String, hex::FromHexError>;
 
-    /// Process a field based on field number, length, and name.
-    fn process_field(&mut self, field_number: u32, length: u32, name: &str, mode: &Mode);
+    /// The hex string with its digits in uppercase, the case used by the parse results.
+    fn canonical_hex(&self) -> String;
+
+    /// Convert a hex string to ASCII, replacing the bytes that are not printable ASCII
+    /// characters with `.` like a hex dump does.
+    fn hex_to_ascii_printable(&mut self) -> Result<String, hex::FromHexError>;
+
+    /// Convert a hex string of EBCDIC (code page 037) characters to text.
+    fn hex_to_ebcdic(&mut self) -> Result<String, hex::FromHexError>;
+
+    /// Process a field based on field number, length, and definition.
+    fn process_field(&mut self, field_number: u32, length: u32, spec: &FieldSpec, mode: &Mode) -> Result<ParsedField, Iso8583Error>;
 
     /// Parse LTV (Length, Tag, Value) format.
-    fn parse_private_ltv(&mut self) -> Result<Vec<LTV>, Box<dyn error::Error>>;
+    fn parse_private_ltv(&mut self) -> Result<Vec<LTV>, Iso8583Error>;
 
-    /// Parse Private TLV format
-    fn parse_private_tlv(&mut self) -> Result<Vec<PrivateTlv>, Box<dyn error::Error>>;
+    /// Parse Private TLV format.
+    ///
+    /// Trailing padding, either shorter than a tag and a length or made only of zeros or
+    /// spaces, is left in the string instead of being parsed.
+    fn parse_private_tlv(&mut self) -> Result<Vec<PrivateTlv>, Iso8583Error>;
+
+    /// Parse private TLV elements laid out as described by `format`, leaving the trailing
+    /// padding like `parse_private_tlv`.
+    fn parse_private_tlv_with(&mut self, format: &PrivateTlvFormat) -> Result<Vec<PrivateTlv>, Iso8583Error>;
 }
 
 impl StringManipulation for String {
     /// Get a slice of the string until a specified length.
-    fn get_slice_until(&mut self, length: usize) -> String {
-        self.drain(..length).collect::<String>()
+    fn get_slice_until(&mut self, length: usize) -> Result<String, Iso8583Error> {
+        if length > self.len() {
+            return Err(Iso8583Error::TruncatedField { bit: 0, needed: length, available: self.len() });
+        }
+        Ok(self.drain(..length).collect::<String>())
     }
 
     /// Convert a hex string to ASCII.
@@ -96,68 +2948,81 @@ impl StringManipulation for String {
         Ok(ascii_chars)
     }
 
-    /// Process a field based on field number, length, and name.
-    fn process_field(&mut self, field_number: u32,length: u32,name: &str, mode: &Mode) {
-        let mut field_value = if field_number == 35 {
-            self.get_slice_until(38 as usize)
-        } else {
-            self.get_slice_until(length as usize)
-        };
+    /// The hex string with its digits in uppercase.
+    fn canonical_hex(&self) -> String {
+        self.to_ascii_uppercase()
+    }
 
-        let value_to_print = if matches!(field_number, 37 | 38 | 41 | 42 | 44 | 49 | 50 | 51 | 62 | 122) {
-            field_value.hex_to_ascii().unwrap()
-        } else {
-            field_value.to_string()
-        };
+    /// Convert a hex string to ASCII, replacing the bytes that are not printable.
+    fn hex_to_ascii_printable(&mut self) -> Result<String, hex::FromHexError> {
+        let hex_bytes = hex::decode(self)?;
+        let ascii_chars: String = hex_bytes
+            .iter()
+            .map(|&byte| if is_printable(byte as char) { byte as char } else { '.' })
+            .collect();
+        Ok(ascii_chars)
+    }
 
-        println!("Field {:3} | Length: {:3}| {:25} | {}", field_number, length, name, value_to_print);
-        
-        if field_number == 55 {
-            match parse_tlv(value_to_print) {
-                Ok(tags) => tags.iter().for_each(|tag| println!("{}", tag)),
-                Err(e) => eprintln!("Error parsing TLV: {}", e),
-            }
-        }
-        else if field_number == 48  {
-            if mode.enabled_private_tlv {
-                let mut tlv_private_value = value_to_print;
-                match tlv_private_value.parse_private_tlv() {
-                    Ok(tlvs_p) => tlvs_p.iter().for_each(|tlv_p| println!("{}", tlv_p)),
-                    Err(e) => eprintln!("Error parsing private tlv: {:?}", e),
-                }
-            }
-            else if mode.enabled_private_ltv {
-                let mut ltv_value = value_to_print;
-                match ltv_value.parse_private_ltv() {
-                    Ok(ltvs) => ltvs.iter().for_each(|ltv| println!("{}", ltv)),
-                    Err(e) => eprintln!("Error parsing LTV: {:?}", e),
-                }
-            }
-        }
+    /// Convert a hex string of EBCDIC (code page 037) characters to text.
+    fn hex_to_ebcdic(&mut self) -> Result<String, hex::FromHexError> {
+        let hex_bytes = hex::decode(self)?;
+        Ok(hex_bytes.iter().map(|&byte| CP037[byte as usize]).collect())
+    }
+
+    /// Process a field based on field number, length, and definition.
+    fn process_field(&mut self, field_number: u32, length: u32, spec: &FieldSpec, mode: &Mode) -> Result<ParsedField, Iso8583Error> {
+        let field_value = self.get_slice_until(spec.wire_length(length) as usize).map_err(|e| e.in_field(field_number))?;
+        decode_field(field_number, length, field_value, spec, mode)
     }
 
 
-    fn parse_private_ltv(&mut self) -> Result<Vec<LTV>, Box<dyn error::Error>> {
+    fn parse_private_ltv(&mut self) -> Result<Vec<LTV>, Iso8583Error> {
     let mut ltvs = Vec::new();
-        while self.len() > 0 {
-            let length =  self.drain(..2).collect::<String>().parse::<usize>()?;
-            let tag =  self.drain(..2).collect::<String>().parse::<u8>()?;
+        while !self.is_empty() {
+            let length =  self.get_slice_until(2)?.parse::<usize>()?;
+            let tag =  self.get_slice_until(2)?.parse::<u8>()?;
+            if length == 0 {
+                return Err(Iso8583Error::InvalidLtv(format!("tag {} has a length of 0", tag)));
+            }
             let byte_length  = (length - 1) * 2;
-            let value = self.drain(..byte_length).collect::<String>();
+            if byte_length > self.len() {
+                return Err(Iso8583Error::InvalidLtv(format!(
+                    "tag {} needs {} characters but only {} are available", tag, byte_length, self.len()
+                )));
+            }
+            let value = self.get_slice_until(byte_length)?;
             let ltv = LTV { length, tag, value};
             ltvs.push(ltv);
         }
     Ok(ltvs)
     }
 
-    fn parse_private_tlv(&mut self) -> Result<Vec<PrivateTlv>, Box<dyn error::Error>> {
+    fn parse_private_tlv(&mut self) -> Result<Vec<PrivateTlv>, Iso8583Error> {
+        self.parse_private_tlv_with(&PrivateTlvFormat::default())
+    }
+
+    /// Parse private TLV elements laid out as described by `format`.
+    fn parse_private_tlv_with(&mut self, format: &PrivateTlvFormat) -> Result<Vec<PrivateTlv>, Iso8583Error> {
         let mut private_tlvs = Vec::new();
-            while self.len() > 0 {
-                let tag =  self.drain(..4).collect::<String>().hex_to_ascii().unwrap();
-                let length_hex_string =  self.drain(..4).collect::<String>().hex_to_ascii().unwrap();
-                let length = usize::from_str_radix(length_hex_string.as_str(), 16)?;
+        // The trailing 00 or space bytes, which end the elements once only they remain
+        let padding = if self.len().is_multiple_of(2) {
+            self.as_bytes().rchunks(2).take_while(|&byte| byte == b"00" || byte == b"20").count() * 2
+        } else {
+            0
+        };
+            while self.len() > padding {
+                if self.len() < (format.tag_bytes + format.length_bytes) * 2 {
+                    break;
+                }
+                let tag = PrivateTlvFormat::decode(format.tag_encoding, self.get_slice_until(format.tag_bytes * 2)?)?;
+                let length = format.decode_length(self.get_slice_until(format.length_bytes * 2)?)?;
                 let byte_length  = length * 2;
-                let value = self.drain(..byte_length).collect::<String>().hex_to_ascii().unwrap();
+                if byte_length > self.len() {
+                    return Err(Iso8583Error::InvalidTlv(format!(
+                        "tag {} needs {} characters but only {} are available", tag, byte_length, self.len()
+                    )));
+                }
+                let value = PrivateTlvFormat::decode(format.value_encoding, self.get_slice_until(byte_length)?)?;
                 let private_tlv = PrivateTlv { tag, length, value};
                 private_tlvs.push(private_tlv);
             }
@@ -166,12 +3031,12 @@ impl StringManipulation for String {
 
 }
 
-use std::fmt;
 impl fmt::Display for LTV {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let value_string = match self.value.clone().hex_to_ascii() {
+        // The value is shown in hex, followed by its text when it is valid hex
+        let value_string = match self.value.clone().hex_to_ascii_printable() {
             Ok(ascii) => format!("-> {}", ascii),
-            Err(e) => e.to_string(), // Handle the error case, you might want to log or handle it differently
+            Err(_) => "-> (non-ascii)".to_string(),
         };
         write!(
             f,
@@ -184,6 +3049,103 @@ impl fmt::Display for LTV {
     }
 }
 
+impl fmt::Display for DecodedMti {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} / {} / {} / {}", self.version, self.class, self.function, self.origin)
+    }
+}
+
+impl fmt::Display for ProcessingCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\tTransaction Type: {} | From Account: {} | To Account: {}",
+            self.transaction_type,
+            self.from_account,
+            self.to_account,
+        )
+    }
+}
+
+impl fmt::Display for PosEntryMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\t{} / {}", self.pan_entry_mode, self.pin_entry_capability)
+    }
+}
+
+impl fmt::Display for CardAcceptorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\tName: {} | City: {} | State/Country: {}", self.name, self.city, self.state_or_country)
+    }
+}
+
+impl fmt::Display for Track2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\tPAN: {} | Expiry: {} | Service Code: {} | Discretionary Data: {}",
+            self.pan,
+            self.expiry,
+            self.service_code,
+            self.discretionary_data,
+        )
+    }
+}
+
+impl fmt::Display for ResponseCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ResponseCategory::Approved => "approved",
+            ResponseCategory::Referral => "referral",
+            ResponseCategory::Declined => "declined",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for OriginalDataElements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\tOriginal MTI: {} | STAN: {} | Date and Time: {} | Acquirer ID: {} | Forwarding ID: {}",
+            self.mti,
+            self.stan,
+            self.transmission_date_time,
+            self.acquirer_id,
+            self.forwarding_id,
+        )
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Summary:")?;
+        writeln!(f, "\tMessages: {} | Errors: {}", self.messages, self.errors)?;
+        for (mti, count) in &self.by_mti {
+            writeln!(f, "\tMTI {}: {}", mti, count)?;
+        }
+        for (code, count) in &self.by_response_code {
+            writeln!(f, "\tResponse Code {}: {}", code, count)?;
+        }
+        if let Some(average) = self.average_amount {
+            writeln!(f, "\tAmounts: {} | Total: {} | Average: {}", self.amounts, self.total_amount, average)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParserResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&FormatOptions::default()))
+    }
+}
+
+impl fmt::Display for ParsedField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&FormatOptions::default()))
+    }
+}
+
 impl fmt::Display for PrivateTlv {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -231,6 +3193,53 @@ mod tests {
         assert_eq!(ltv2.value.hex_to_ascii().unwrap(), "World");
     }
 
+    // The examples of the module documentation, kept here so that changing the API they use
+    // fails a test that has to be updated on purpose
+    #[test]
+    fn test_doc_examples() {
+        let mut s = String::from("48656C6C6F2C576F726C64");
+        assert_eq!(s.hex_to_ascii().unwrap(), "Hello,World");
+        assert_eq!(s.get_slice_until(5).unwrap(), "48656");
+        assert_eq!(s.get_slice_until(5).unwrap(), "C6C6F");
+        assert_eq!(s, "2C576F726C64");
+        assert!(s.get_slice_until(50).is_err());
+
+        let bitmap = crate::positions_of_set_bits(u64::from_str_radix("3038058020C19201", 16).unwrap());
+        assert_eq!(bitmap, vec![3, 4, 11, 12, 13, 22, 24, 25, 35, 41, 42, 48, 49, 52, 55, 64]);
+
+        let ltv_hex = "1101303830303539313535301002322E362E31352E3332020330022231021532";
+        let expected = vec![
+            crate::LTV { length: 11, tag: 1, value: "30383030353931353530".to_string() },
+            crate::LTV { length: 10, tag: 2, value: "322E362E31352E3332".to_string() },
+            crate::LTV { length: 2, tag: 3, value: "30".to_string() },
+            crate::LTV { length: 2, tag: 22, value: "31".to_string() },
+            crate::LTV { length: 2, tag: 15, value: "32".to_string() },
+        ];
+        assert_eq!(ltv_hex.to_string().parse_private_ltv().unwrap(), expected);
+        assert_eq!(expected[0].to_string(), "\tLen:  11 | Tag:   1 | Val: 30383030353931353530 -> 0800591550");
+        assert_eq!(expected[1].to_string(), "\tLen:  10 | Tag:   2 | Val: 322E362E31352E3332 -> 2.6.15.32");
+
+        // The same elements in field 48 of a whole message
+        let message = format!("010000000000000100000032{}", ltv_hex);
+        let mode = crate::Mode { enabled_private_ltv: true, ..Default::default() };
+        let result = crate::parse_iso8583(&message, false, &mode, None).unwrap();
+        assert_eq!(result.field(48).unwrap().value, ltv_hex);
+        assert_eq!(result.field(48).unwrap().sub_elements, Some(crate::SubElements::Ltv(expected)));
+        assert_eq!(result.unparsed, "");
+    }
+
+    #[test]
+    fn test_ltv_display() {
+        let ltv = crate::LTV { length: 6, tag: 11, value: "48656C6C6F".to_string() };
+        assert_eq!(ltv.to_string(), "\tLen:   6 | Tag:  11 | Val: 48656C6C6F -> Hello");
+
+        let ltv = crate::LTV { length: 3, tag: 11, value: "0A41".to_string() };
+        assert_eq!(ltv.to_string(), "\tLen:   3 | Tag:  11 | Val: 0A41 -> .A");
+
+        let ltv = crate::LTV { length: 3, tag: 11, value: "4G69".to_string() };
+        assert_eq!(ltv.to_string(), "\tLen:   3 | Tag:  11 | Val: 4G69 -> (non-ascii)");
+    }
+
     #[test]
     fn test_parse_ltv_empty() {
         let mut s = String::new();
@@ -240,6 +3249,281 @@ mod tests {
         assert!(ltvs.unwrap().is_empty());
     }
 
+    #[test]
+    fn test_parse_private_tlv_trailing_padding() {
+        let mut s = String::from("3031303348656C0000");
+        let tlvs = s.parse_private_tlv().unwrap();
+        assert_eq!(tlvs.len(), 1);
+        assert_eq!(tlvs[0].tag, "01");
+        assert_eq!(tlvs[0].value, "Hel");
+        assert_eq!(s, "0000");
+
+        let mut s = String::from("3031303348656C2020202020");
+        assert_eq!(s.parse_private_tlv().unwrap().len(), 1);
+        assert_eq!(s, "2020202020");
+
+        // Padding bytes at the end of a value belong to it
+        let mut s = String::from("303130334100000000");
+        assert_eq!(s.parse_private_tlv().unwrap()[0].value, "A\0\0");
+        assert_eq!(s, "0000");
+
+        let mut s = String::from("3031303548656C");
+        assert_eq!(s.parse_private_tlv().unwrap_err(), crate::Iso8583Error::InvalidTlv("tag 01 needs 10 characters but only 6 are available".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_hex() {
+        assert_eq!(crate::normalize_hex("\"01 00\"\n0xab:Cd\t0Xef").unwrap(), "0100ABCDEF");
+        assert_eq!(
+            crate::normalize_hex("0100 2Z00").unwrap_err(),
+            crate::Iso8583Error::InvalidHex(hex::FromHexError::InvalidHexCharacter { c: 'Z', index: 6 })
+        );
+        assert_eq!(crate::normalize_hex("0100x1").unwrap_err().to_string(), "Invalid hex: Invalid character 'x' at position 4");
+        assert_eq!(crate::normalize_hex("010").unwrap_err(), crate::Iso8583Error::InvalidHex(hex::FromHexError::OddLength));
+    }
+
+    #[test]
+    fn test_decode_mti() {
+        let mti = crate::decode_mti("0200").unwrap();
+        assert_eq!(mti.to_string(), "1987 / Financial / Request / Acquirer");
+
+        let mti = crate::decode_mti("1810").unwrap();
+        assert_eq!(mti.version, "1993");
+        assert_eq!(mti.class, "Network Management");
+        assert_eq!(mti.function, "Request Response");
+        assert_eq!(mti.origin, "Acquirer");
+
+        assert!(crate::decode_mti("00A8").is_err());
+        assert!(crate::decode_mti("020").is_err());
+    }
+
+    #[test]
+    fn test_parse_processing_code() {
+        let code = crate::parse_processing_code("001000").unwrap();
+        assert_eq!(code.transaction_type, "Purchase");
+        assert_eq!(code.from_account, "Savings");
+        assert_eq!(code.to_account, "Default");
+
+        let code = crate::parse_processing_code("772099").unwrap();
+        assert_eq!(code.transaction_type, "77");
+        assert_eq!(code.from_account, "Checking");
+        assert_eq!(code.to_account, "99");
+
+        assert!(crate::parse_processing_code("0010").is_err());
+    }
+
+    #[test]
+    fn test_parse_track2() {
+        let track2 = crate::parse_track2("8362143741176336D28112011861000000000F").unwrap();
+        assert_eq!(track2.pan, "8362143741176336");
+        assert_eq!(track2.expiry, "2811");
+        assert_eq!(track2.service_code, "201");
+        assert_eq!(track2.discretionary_data, "1861000000000");
+
+        let track2 = crate::parse_track2("4111111111111111=25122011234567890").unwrap();
+        assert_eq!(track2.pan, "4111111111111111");
+        assert_eq!(track2.expiry, "2512");
+        assert_eq!(track2.service_code, "201");
+        assert_eq!(track2.discretionary_data, "1234567890");
+
+        assert!(crate::parse_track2("41111111111111112512201").is_err());
+        assert!(crate::parse_track2("4111111111111111D2512").is_err());
+    }
+
+    #[test]
+    fn test_luhn_is_valid() {
+        assert!(crate::luhn_is_valid("4111111111111111"));
+        assert!(crate::luhn_is_valid("4111 1111 1111 1111"));
+        assert!(crate::luhn_is_valid("6037991234567893"));
+        assert!(!crate::luhn_is_valid("4111111111111112"));
+        assert!(!crate::luhn_is_valid("42"));
+        assert!(!crate::luhn_is_valid("41111111111111111111"));
+    }
+
+    #[test]
+    fn test_mask_pan() {
+        assert_eq!(crate::mask_pan("4111111111111111"), "411111******1111");
+        assert_eq!(crate::mask_pan("6037991234567893012"), "603799*********3012");
+        assert_eq!(crate::mask_pan("4111111111"), "4111111111");
+        assert_eq!(crate::mask_track2("8362143741176336D28112011861000000000F"), "836214******6336D28112011861000000000F");
+        assert_eq!(crate::mask_track1("B4111111111111111^DOE/JOHN^2512201"), "B411111******1111^DOE/JOHN^2512201");
+        assert_eq!(crate::mask_track1(&hex::encode_upper("B4111111111111111^DOE/JOHN^")), hex::encode_upper("B411111******1111^DOE/JOHN^"));
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        use crate::{parse_iso8583, Iso8583Error, Mode};
+        let mode = Mode::default();
+        assert_eq!(
+            parse_iso8583("0013600008000001002000000000000000930000", true, &mode, None).unwrap_err(),
+            Iso8583Error::LengthMismatch { expected: 38, actual: 36 }
+        );
+        assert_eq!(
+            parse_iso8583("01000100000000000000023132", false, &mode, None).unwrap_err(),
+            Iso8583Error::UnimplementedField(8)
+        );
+        assert!(matches!(parse_iso8583("0100Z000000000000000930000", false, &mode, None), Err(Iso8583Error::InvalidHex(_))));
+        assert!(matches!(parse_iso8583("01002000000000000000930", false, &mode, None), Err(Iso8583Error::InvalidHex(_))));
+    }
+
+    #[test]
+    fn test_truncated_field() {
+        use crate::{parse_iso8583, Iso8583Error, Mode};
+        let mode = Mode::default();
+        // Field 2 claims 16 digits but only 6 remain
+        assert_eq!(
+            parse_iso8583("0200400000000000000016411111", false, &mode, None).unwrap_err(),
+            Iso8583Error::TruncatedField { bit: 2, needed: 16, available: 6 }
+        );
+        // The length prefix itself is cut
+        assert_eq!(
+            parse_iso8583("02004000000000000000", false, &mode, None).unwrap_err(),
+            Iso8583Error::TruncatedField { bit: 2, needed: 2, available: 0 }
+        );
+        // The bitmap is cut
+        assert_eq!(
+            parse_iso8583("02004000", false, &mode, None).unwrap_err(),
+            Iso8583Error::TruncatedField { bit: 0, needed: 16, available: 4 }
+        );
+        assert!(String::from("1231").parse_private_ltv().is_err());
+    }
+
+    #[test]
+    fn test_parse_ltv_invalid_length() {
+        use crate::Iso8583Error;
+        let mut s = String::from("001148656C6C6F");
+        assert_eq!(s.parse_private_ltv().unwrap_err(), Iso8583Error::InvalidLtv("tag 11 has a length of 0".to_string()));
+
+        let mut s = String::from("091148656C6C6F");
+        assert_eq!(
+            s.parse_private_ltv().unwrap_err(),
+            Iso8583Error::InvalidLtv("tag 11 needs 16 characters but only 10 are available".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hex_to_ebcdic() {
+        let mut s = String::from("E3C5D9D4F0F0F0F1");
+        assert_eq!(s.hex_to_ebcdic().unwrap(), "TERM0001");
+        let mut s = String::from("4B4E5A7D");
+        assert_eq!(s.hex_to_ebcdic().unwrap(), ".+!'");
+    }
+
+    #[test]
+    fn test_process_field_invalid_hex() {
+        use crate::{Encoding, FieldSpec, Iso8583Error, LengthType, Mode};
+        let spec = FieldSpec::new("Acceptor", LengthType::Fixed, 15, Encoding::Ascii);
+        let mut s = String::from("3031323");
+        assert_eq!(
+            s.process_field(42, 7, &spec, &Mode::default()).unwrap_err(),
+            Iso8583Error::InvalidField { bit: 42, reason: "Odd number of digits".to_string() }
+        );
+        let mut s = String::from("30G1");
+        assert!(matches!(s.process_field(42, 4, &spec, &Mode::default()), Err(Iso8583Error::InvalidField { bit: 42, .. })));
+    }
+
+    #[test]
+    fn test_describe_response_code() {
+        assert_eq!(crate::describe_response_code("00"), Some("Approved"));
+        assert_eq!(crate::describe_response_code("51"), Some("Insufficient Funds"));
+        assert_eq!(crate::describe_response_code("116"), Some("Insufficient Funds"));
+        assert_eq!(crate::describe_response_code("X9"), None);
+        assert_eq!(crate::response_code("3035"), "05");
+        assert_eq!(crate::response_code("313136"), "116");
+        assert_eq!(crate::response_code("05"), "05");
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut cursor = crate::Cursor::new("0100200000");
+        assert_eq!(cursor.take(4).unwrap(), "0100");
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(cursor.remaining(), 6);
+        assert_eq!(cursor.take(8).unwrap_err(), crate::Iso8583Error::TruncatedField { bit: 0, needed: 8, available: 6 });
+        assert_eq!(cursor.rest(), "200000");
+        assert_eq!(cursor.peek(2).unwrap(), "20");
+        assert_eq!(cursor.position(), 4);
+        assert!(cursor.peek(7).is_err());
+        assert_eq!(cursor.take(6).unwrap(), "200000");
+        assert_eq!(cursor.data(), "0100200000");
+        assert_eq!(cursor.remaining(), 0);
+    }
+
+    #[test]
+    fn test_decode_tvr() {
+        assert_eq!(crate::decode_tvr(&[0x80, 0, 0, 0, 0]), vec!["Offline data authentication was not performed"]);
+        assert_eq!(crate::decode_tvr(&[0, 0x08, 0x08, 0x80, 0x40]), vec!["New card", "PIN entry required, PIN pad present, but PIN was not entered", "Transaction exceeds floor limit", "Issuer authentication failed"]);
+        assert!(crate::decode_tvr(&[0x01, 0x07, 0x03, 0x07, 0x0F]).is_empty());
+        assert!(crate::decode_tvr(&[0; 5]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_pos_entry_mode() {
+        let mode = crate::parse_pos_entry_mode("051").unwrap();
+        assert_eq!(mode.to_string(), "\tChip / PIN capable");
+        let mode = crate::parse_pos_entry_mode("0072").unwrap();
+        assert_eq!(mode.pan_entry_mode, "Contactless Chip");
+        assert_eq!(mode.pin_entry_capability, "Not PIN capable");
+        let mode = crate::parse_pos_entry_mode("665").unwrap();
+        assert_eq!(mode.pan_entry_mode, "66");
+        assert_eq!(mode.pin_entry_capability, "5");
+        assert!(crate::parse_pos_entry_mode("05").is_err());
+        assert!(crate::parse_pos_entry_mode("05A1").is_err());
+    }
+
+    #[test]
+    fn test_format_date_time() {
+        use crate::{format_field12, format_field13, format_field7, Iso8583Error};
+        assert_eq!(format_field7("1125160400").unwrap(), "11-25 16:04:00");
+        assert_eq!(format_field12("160400").unwrap(), "16:04:00");
+        assert_eq!(format_field13("1125").unwrap(), "11-25");
+        assert_eq!(
+            format_field7("1325160400").unwrap_err(),
+            Iso8583Error::InvalidField { bit: 7, reason: "month 13 is out of range".to_string() }
+        );
+        assert!(format_field12("246000").is_err());
+        assert!(format_field13("0132").is_err());
+        assert!(format_field13("11250").is_err());
+    }
+
+    #[test]
+    fn test_currency_alpha_code() {
+        assert_eq!(crate::currency_alpha_code("840"), Some("USD"));
+        assert_eq!(crate::currency_alpha_code("364"), Some("IRR"));
+        assert_eq!(crate::currency_alpha_code("999"), None);
+    }
+
+    #[test]
+    fn test_format_amount() {
+        use crate::format_amount;
+        assert_eq!(format_amount("000000012345", None), "123.45");
+        assert_eq!(format_amount("000000000001", Some("364")), "0.01");
+        assert_eq!(format_amount("000000012345", Some("JPY")), "12345");
+        assert_eq!(format_amount("000000012345", Some("048")), "12.345");
+        assert_eq!(format_amount("000000000000", Some("392")), "0");
+        assert_eq!(format_amount("00000000ABCD", None), "00000000ABCD");
+        assert_eq!(
+            crate::format_additional_amounts("1002364D000000012345").unwrap(),
+            vec!["\tAccount Type: 10 | Amount Type: 02 | Currency: 364 | Amount: -123.45"]
+        );
+        assert!(crate::format_additional_amounts("1002364D").is_err());
+    }
+
+    #[test]
+    fn test_parse_card_acceptor_location() {
+        let location = crate::parse_card_acceptor_location("ACME STORE               TEHRAN       IR").unwrap();
+        assert_eq!(location.name, "ACME STORE");
+        assert_eq!(location.city, "TEHRAN");
+        assert_eq!(location.state_or_country, "IR");
+
+        let location = crate::parse_card_acceptor_location("SHORT NAME").unwrap();
+        assert_eq!(location.name, "SHORT NAME");
+        assert_eq!(location.city, "");
+        assert_eq!(location.state_or_country, "");
+
+        assert!(crate::parse_card_acceptor_location(&"X".repeat(41)).is_err());
+    }
+
     #[test]
     fn error_test() {
         let mut s = String::from("T31148690622576F726C64");
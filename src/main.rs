@@ -1,4 +1,6 @@
-use iso8583_parser::{StringManipulation, positions_of_set_bits, Mode};
+use iso8583_parser::{summarize, BatchSummary, BitmapFormat, ParserResult, parse_iso8583, parse_iso8583_auto, parse_iso8583_stream, server, FieldCatalog, EXAMPLE_MESSAGES, FormatOptions, HexCase, InputFormat, LengthCheck, LengthEncoding, LengthHeaderKind, Mode, NumericEncoding, PrivateTlvFormat, TlvErrorPolicy};
+#[cfg(feature = "serde")]
+use iso8583_parser::parse_iso8583_json;
 use clap::Parser;
 
 fn read_data_from_stdin()-> String {
@@ -16,6 +18,125 @@ fn read_data_from_stdin()-> String {
     data_raw
 }
 
+/// Parse a `BIT=NAME` field name override.
+fn parse_field_name(s: &str) -> Result<(u32, String), String> {
+    let (bit, name) = s.split_once('=').ok_or_else(|| format!("expected BIT=NAME but got {:?}", s))?;
+    let bit = bit.trim().parse().map_err(|e| format!("invalid bit {:?}: {}", bit, e))?;
+    Ok((bit, name.to_string()))
+}
+
+fn parse_length_check(s: &str) -> Result<LengthCheck, String> {
+    match s {
+        "exact" => Ok(LengthCheck::Exact),
+        "at-least" => Ok(LengthCheck::AtLeast),
+        "ignore" => Ok(LengthCheck::Ignore),
+        _ => Err(format!("expected exact, at-least or ignore but got {:?}", s)),
+    }
+}
+
+fn parse_input_format(s: &str) -> Result<InputFormat, String> {
+    match s {
+        "hex" => Ok(InputFormat::Hex),
+        "base64" => Ok(InputFormat::Base64),
+        "raw" => Ok(InputFormat::Raw),
+        _ => Err(format!("expected hex, base64 or raw but got {:?}", s)),
+    }
+}
+
+fn parse_bitmap_format(s: &str) -> Result<BitmapFormat, String> {
+    match s {
+        "positions" => Ok(BitmapFormat::Positions),
+        "hex" => Ok(BitmapFormat::Hex),
+        "both" => Ok(BitmapFormat::Both),
+        _ => Err(format!("expected positions, hex or both but got {:?}", s)),
+    }
+}
+
+fn parse_tlv_error_policy(s: &str) -> Result<TlvErrorPolicy, String> {
+    match s {
+        "ignore" => Ok(TlvErrorPolicy::Ignore),
+        "raw" => Ok(TlvErrorPolicy::Raw),
+        "fail" => Ok(TlvErrorPolicy::Fail),
+        _ => Err(format!("expected ignore, raw or fail but got {:?}", s)),
+    }
+}
+
+fn parse_private_tlv_format(s: &str) -> Result<PrivateTlvFormat, String> {
+    match s {
+        "ascii" => Ok(PrivateTlvFormat::ascii()),
+        "binary" => Ok(PrivateTlvFormat::binary()),
+        _ => Err(format!("expected ascii or binary but got {:?}", s)),
+    }
+}
+
+/// When to color the output with ANSI escape codes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+fn parse_color(s: &str) -> Result<ColorChoice, String> {
+    match s {
+        "auto" => Ok(ColorChoice::Auto),
+        "always" => Ok(ColorChoice::Always),
+        "never" => Ok(ColorChoice::Never),
+        _ => Err(format!("expected auto, always or never but got {:?}", s)),
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether to color the output: with `auto`, only on a terminal and when `NO_COLOR` is not set.
+fn use_color(choice: ColorChoice) -> bool {
+    use std::io::IsTerminal;
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stdout().is_terminal(),
+    }
+}
+
+fn paint(text: &str, color: &str, enabled: bool) -> String {
+    if enabled {
+        format!("{}{}{}", color, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Highlight the MTI, the field numbers and the warnings of a formatted report.
+fn colorize_report(report: &str) -> String {
+    let mut output = String::new();
+    let mut in_warnings = false;
+    for line in report.lines() {
+        if line.starts_with("Warnings:") {
+            in_warnings = true;
+        } else if !line.starts_with('\t') {
+            in_warnings = false;
+        }
+        let colored = match line.split_once('|') {
+            _ if in_warnings || line.starts_with("Not parsed Part:") => paint(line, YELLOW, true),
+            _ if line.starts_with("MTI:") => paint(line, CYAN, true),
+            Some((number, rest)) if line.starts_with("Field ") => format!("{}|{}", paint(number, GREEN, true), rest),
+            _ => line.to_string(),
+        };
+        output.push_str(&colored);
+        output.push('\n');
+    }
+    output
+}
+
+/// One line report of a parsed message for --quiet.
+fn quiet_line(result: &ParserResult) -> String {
+    format!("OK: MTI {}, {} fields", result.mti, result.fields.len())
+}
+
 /// Arguments
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -24,125 +145,395 @@ struct Args {
     #[arg(short, long, required = false)]
     message: Option<String>,
 
+    /// how the message given with -m or -f is encoded: hex, base64 or raw (the bytes of the
+    /// message themselves)
+    #[arg(long, value_name = "FORMAT", default_value = "hex", value_parser = parse_input_format)]
+    input_format: InputFormat,
+
+    /// shorthand for --input-format base64
+    #[arg(long, conflicts_with = "input_format")]
+    base64: bool,
+
+    /// read the message(s) from a file, ignoring whitespace and newlines
+    #[arg(short, long, value_name = "PATH", conflicts_with = "message")]
+    file: Option<std::path::PathBuf>,
+
+    /// parse each line of a file as a separate message and print a summary per line,
+    /// skipping empty lines and lines starting with '#'
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["message", "file"])]
+    batch_file: Option<std::path::PathBuf>,
+
     #[arg(short, long)]
     including_header_length: bool,
 
+    /// detect whether the message starts with a length header and a header
+    #[arg(long, conflicts_with = "including_header_length")]
+    auto_header: bool,
+
+    /// number of hex characters of the header following the length header given with -i
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    header_len: usize,
+
+    /// read the length header given with -i as 4 ASCII decimal digits instead of 2 binary bytes
+    #[arg(long)]
+    ascii_mli: bool,
+
+    /// how the length given by the length header is checked: exact, at-least (trailing bytes
+    /// such as an LRC are left unparsed) or ignore
+    #[arg(long, value_name = "CHECK", default_value = "exact", value_parser = parse_length_check)]
+    length_check: LengthCheck,
+
+    /// check the LRC byte following each message, the XOR of all the bytes before it
+    #[arg(long)]
+    lrc: bool,
+
+    /// remove the STX and ETX bytes wrapping the message, and the LRC following the ETX
+    #[arg(long)]
+    strip_framing: bool,
+
     #[arg(short, long)]
     tlv_private: bool,
 
     #[arg(short, long)]
     ltv_private: bool,
+
+    /// layout of the private TLV elements given with -t: ascii (2-character tags and ASCII
+    /// hex lengths) or binary (1-byte tags and lengths)
+    #[arg(long, value_name = "FORMAT", default_value = "ascii", value_parser = parse_private_tlv_format)]
+    tlv_format: PrivateTlvFormat,
+
+    /// try private TLV parsing of fields 48 and 121 when neither -t nor -l is given
+    #[arg(short, long)]
+    auto_private: bool,
+
+    /// keep the fields parsed before an unimplemented field instead of failing
+    #[arg(long)]
+    lenient: bool,
+
+    /// mask the PAN of fields 2, 35 and 45
+    #[arg(short = 'p', long)]
+    mask_pan: bool,
+
+    /// read the length prefixes of variable length fields as ASCII digits instead of BCD
+    #[arg(long)]
+    ascii_length: bool,
+
+    /// read numeric fields as packed BCD digits, as defined by the field catalog (default)
+    #[arg(long, conflicts_with = "ascii")]
+    bcd: bool,
+
+    /// read numeric fields as ASCII digits instead of packed BCD
+    #[arg(long)]
+    ascii: bool,
+
+    /// name to show for a field instead of its standard name, e.g. 63=Loyalty Data
+    #[arg(long, value_name = "BIT=NAME", value_parser = parse_field_name)]
+    field_name: Vec<(u32, String)>,
+
+    /// what to do with EMV data of field 55 that can not be decoded: ignore, raw (also show
+    /// the undecoded hex) or fail
+    #[arg(long, value_name = "POLICY", default_value = "ignore", value_parser = parse_tlv_error_policy)]
+    tlv_errors: TlvErrorPolicy,
+
+    /// width of the field name column
+    #[arg(long, default_value_t = 25)]
+    name_width: usize,
+
+    /// show the raw hex next to the decoded value of text fields
+    #[arg(short = 'r', long)]
+    raw: bool,
+
+    /// show the bitmap as the positions of the fields present, as hex or both
+    #[arg(long, value_name = "FORMAT", default_value = "positions", value_parser = parse_bitmap_format)]
+    bitmap_format: BitmapFormat,
+
+    /// print the hex digits of field values and of their details in lowercase
+    #[arg(long)]
+    lowercase: bool,
+
+    /// show where each field starts and ends in the message, in hex characters
+    #[arg(long)]
+    offsets: bool,
+
+    /// print the characters of field values that are not printable ASCII as they are instead
+    /// of as '.'
+    #[arg(long)]
+    control_chars: bool,
+
+    /// print only "OK" with the MTI and the number of fields, or the error, for each message
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// color the output: auto (on a terminal unless NO_COLOR is set), always or never
+    #[arg(long, value_name = "WHEN", default_value = "auto", value_parser = parse_color)]
+    color: ColorChoice,
+
+    /// only show the fields whose number or name contains this text
+    #[arg(long, value_name = "QUERY")]
+    filter: Option<String>,
+
+    /// print the parsed message as JSON, or an array with one element per message when the
+    /// input holds several
+    #[cfg(feature = "serde")]
+    #[arg(long)]
+    json: bool,
+
+    /// compare the message field by field with another one
+    #[arg(long, value_name = "OTHER_MESSAGE")]
+    diff: Option<String>,
+
+    /// list the fields that can be parsed and exit
+    #[arg(long)]
+    list_fields: bool,
+
+    /// list the example messages, each with its name, the message and the options it needs
+    /// separated by tabs, and exit
+    #[arg(long)]
+    list_examples: bool,
+
+    /// listen on the given address and parse the messages received
+    #[arg(long, value_name = "ADDR")]
+    serve: Option<String>,
 }
 
 fn main() {
     // Get command-line arguments
     let args = Args::parse();
 
-    // Check if message argument is provided unless read data from stdin
-    let mut s = match args.message {
-        Some(m) => m,
-        None => read_data_from_stdin(), 
-    };
-    s = s.replace("\"", "").replace(" ", "");
-    if args.including_header_length {
-        let message_len = u32::from_str_radix(&s.get_slice_until(4), 16).expect("Unable to get the length") * 2;
-        println!("Lentgh Of Message: {}", message_len );
-        if s.len() != message_len as usize {
-            panic!("Error: Incorrect message len. The expected length is {} but The actual is {}", message_len,  s.len());
-        }
-        println!("Header: {}", s.get_slice_until(10));
-    }
-    println!("MTI: {}", s.get_slice_until(4));
-    let mut bitmap: Vec<u32> = positions_of_set_bits(u64::from_str_radix(&s.get_slice_until(16),16).expect("Unable to get the process code"));
-    if bitmap.contains(&1) {
-        let mut positions = positions_of_set_bits(u64::from_str_radix(&s.get_slice_until(16), 16).expect("Unable to get the process code"));
-        positions.iter_mut().for_each(|num| *num += 64);
-        bitmap.append(&mut positions);
-        bitmap.retain(|&x| x != 1);
-    }
-    println!("First Bit Map: {:?}", bitmap);
-    let mode  = Mode { 
+    let mode  = Mode {
         enabled_private_tlv: args.tlv_private,
         enabled_private_ltv: args.ltv_private,
+        mask_pan: args.mask_pan,
+        lenient: args.lenient,
+        auto_detect_private: args.auto_private,
+        length_encoding: if args.ascii_length { LengthEncoding::Ascii } else { LengthEncoding::Bcd },
+        length_header: if args.ascii_mli { LengthHeaderKind::AsciiDecimal } else { LengthHeaderKind::BinaryHex },
+        header_len: args.header_len,
+        length_check: args.length_check,
+        lrc: args.lrc,
+        strip_framing: args.strip_framing,
+        tlv_error_policy: args.tlv_errors,
+        private_tlv_format: args.tlv_format,
+        hex_case: if args.lowercase { HexCase::Lower } else { HexCase::Upper },
+        field_names: args.field_name.into_iter().collect(),
+        numeric_encoding: if args.ascii { NumericEncoding::Ascii } else { NumericEncoding::Bcd },
      };
-        
-     for &bit in &bitmap {
-        match bit {
-            2 => {
-                let pan_len: u32 =  s.get_slice_until(2).parse::<u32>().unwrap();
-                s.process_field(2, pan_len, "PAN", &mode);
+
+    if args.list_fields {
+        for (bit, spec) in FieldCatalog::default().iter() {
+            println!("Field {:3} | {}", bit, spec.name);
+        }
+        return;
+    }
+
+    if args.list_examples {
+        for example in EXAMPLE_MESSAGES {
+            let mut options = Vec::new();
+            if example.including_header_length {
+                options.push("-i");
             }
-            3 => s.process_field(3, 6, "Process Code", &mode),
-            4 => s.process_field(4, 12, "Transaction Amount", &mode),
-            5 => s.process_field(5, 12, "Settlement Amount", &mode),
-            6 => s.process_field(6, 12, "Cardholder Billing Amount", &mode),
-            7 => s.process_field(7, 10, "Transaction Date and Time", &mode),
-            9 => s.process_field(9, 8, "Conversion rate, settlement", &mode),
-            10 => s.process_field(10, 8, "Conversion rate, cardholder billing", &mode),
-            11 => s.process_field(11, 6, "Trace", &mode),
-            12 => s.process_field(12, 6, "Time", &mode),
-            13 => s.process_field(13, 4, "Date", &mode),
-            14 => s.process_field(14, 4, "Card EXpiration Date", &mode),
-            18 => s.process_field(18, 4, "Merchant Category Code", &mode),
-            19 => s.process_field(19, 3, "Acquirer Country Code", &mode),
-            22 => s.process_field(22, 4, "POS Entry Mode", &mode),
-            23 => s.process_field(23, 3, "Card Sequence Number", &mode),
-            24 => s.process_field(24, 4, "", &mode),
-            25 => s.process_field(25, 2, "", &mode),
-            35 => {
-                let track2_len: u32 = s.get_slice_until(2).parse::<u32>().unwrap();
-                s.process_field(35, track2_len, "Track2", &mode);
+            if example.private_tlv {
+                options.push("-t");
             }
-            37 => s.process_field(37, 24, "Retrieval Ref #", &mode),
-            38 => s.process_field(38, 12, "Authorization Code", &mode),
-            39 => s.process_field(39, 4, "Response Code", &mode),
-            41 => s.process_field(41, 16, "Terminal", &mode),
-            42 => s.process_field(42, 30, "Acceptor", &mode),
-            43 => s.process_field(43, 40, "Card Acceptor Name/Location", &mode),
-            44 => {
-                let field44_len: u32 = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(44, field44_len, "Additional response data", &mode);
+            if example.private_ltv {
+                options.push("-l");
             }
-            45 => {
-                let track1_len: u32 = s.get_slice_until(2).parse::<u32>().unwrap();
-                s.process_field(45, track1_len, "Track 1 Data", &mode);
+            println!("{}\t{}\t{}", example.name, example.message, options.join(" "));
+        }
+        return;
+    }
+
+    if let Some(addr) = args.serve {
+        println!("Listening on {}", addr);
+        if let Err(e) = server::serve(&addr, args.including_header_length, &mode) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let color = use_color(args.color);
+
+    if let Some(path) = args.batch_file {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        };
+        let mut parsed = Vec::new();
+        let mut errors = 0;
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
-            48 => {
-                let field48_len = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(48, field48_len, "Aditional Data", &mode);
+            let result = if args.auto_header {
+                parse_iso8583_auto(line, &mode, None).map(|(result, _)| result)
+            } else {
+                parse_iso8583(line, args.including_header_length, &mode, None)
+            };
+            match result {
+                Ok(result) if args.quiet => println!("Line {:4} | {}", i + 1, quiet_line(&result)),
+                Ok(result) => {
+                    println!("Line {:4} | {:4} | OK", i + 1, result.mti);
+                    parsed.push(result);
+                }
+                Err(e) if args.quiet => {
+                    println!("Line {:4} | {}", i + 1, paint(&format!("Error: {}", e), RED, color));
+                    errors += 1;
+                }
+                Err(e) => {
+                    println!("Line {:4} | {:4} | {}", i + 1, "-", paint(&format!("Error: {}", e), RED, color));
+                    errors += 1;
+                }
+            }
+        }
+        if !args.quiet {
+            print!("{}", BatchSummary { errors, ..summarize(&parsed) });
+        }
+        if errors > 0 {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Check if message argument is provided unless read data from stdin
+    let input_format = if args.base64 { InputFormat::Base64 } else { args.input_format };
+    let input = match (args.message, args.file) {
+        (Some(m), _) => m.into_bytes(),
+        (None, Some(path)) => match std::fs::read(&path) {
+            Ok(content) if input_format == InputFormat::Raw => content,
+            Ok(content) => content.into_iter().filter(|c| !c.is_ascii_whitespace()).collect(),
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", path.display(), e);
+                std::process::exit(1);
             }
-            49 => s.process_field(49, 6, "Transaction Currency Code", &mode),
-            50 => s.process_field(50, 6, "Settlement Currency Code", &mode),
-            51 => s.process_field(51, 6, "Billing Currency Code", &mode),
-            52 => s.process_field(52, 16, "PinBlock", &mode),
-            54 => {
-                let field54_len = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(54, field54_len, "Amount", &mode);
+        },
+        (None, None) => read_data_from_stdin().into_bytes(),
+    };
+    // Other formats are turned into hex, which all the outputs below are made from
+    let to_hex = |input: &[u8]| match input_format {
+        InputFormat::Hex => String::from_utf8_lossy(input).into_owned(),
+        _ => match input_format.decode(input) {
+            Ok(bytes) => hex::encode_upper(bytes),
+            Err(e) => {
+                eprintln!("{}", paint(&format!("Error: {}", e), RED, color));
+                std::process::exit(1);
             }
-            55 => {
-                let field55_len = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(55, field55_len, "", &mode);
+        },
+    };
+    let s = to_hex(&input);
+
+    #[cfg(feature = "serde")]
+    if args.json {
+        let including_header_length = if args.auto_header {
+            parse_iso8583_auto(&s, &mode, None).is_ok_and(|(_, framed)| framed)
+        } else {
+            args.including_header_length
+        };
+        // Several messages of a stream are printed as an array, with one element per message
+        let results = parse_iso8583_stream(&s, including_header_length, &mode, None);
+        if results.len() > 1 {
+            #[derive(serde::Serialize)]
+            #[serde(untagged)]
+            enum Message<'a> {
+                Parsed(&'a ParserResult),
+                Failed { error: String },
             }
-            60 => {
-                let field60_len = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(60, field60_len, "", &mode);              
+            let errors = results.iter().filter(|result| result.is_err()).count();
+            let messages: Vec<Message> = results
+                .iter()
+                .map(|result| match result {
+                    Ok(result) => Message::Parsed(result),
+                    Err(e) => Message::Failed { error: e.to_string() },
+                })
+                .collect();
+            match serde_json::to_string_pretty(&messages) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    println!("{}", serde_json::json!({ "error": e.to_string() }));
+                    std::process::exit(1);
+                }
             }
-            62 => {
-                let field62_len = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(62, field62_len, "Private", &mode);
+            if errors > 0 {
+                std::process::exit(1);
             }
-            64 => s.process_field(64, 16, "MAC", &mode),
-            70 => s.process_field(70, 4, "", &mode),
-            122 => {
-                let field122_len = s.get_slice_until(4).parse::<u32>().unwrap() * 2;
-                s.process_field(122, field122_len, "Additional Data", &mode);
+            return;
+        }
+        match parse_iso8583_json(&s, including_header_length, &mode, None) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!("{}", serde_json::json!({ "error": e.to_string() }));
+                std::process::exit(1);
             }
-            128 => s.process_field(128, 16, "MAC", &mode),
-            num => {println!("The number {} is not defined yet.", num); return;},
         }
+        return;
     }
-    if !s.is_empty() {
-        println!("Not parsed Part: {}",s);
+
+    if let Some(other) = args.diff {
+        // The other message is given in the same format and framing as the first one
+        let other = to_hex(other.as_bytes());
+        let parse = |message: &str, name: &str| {
+            let result = if args.auto_header {
+                parse_iso8583_auto(message, &mode, None).map(|(result, _)| result)
+            } else {
+                parse_iso8583(message, args.including_header_length, &mode, None)
+            };
+            result.unwrap_or_else(|e| {
+                eprintln!("Error: could not parse message {}: {}", name, e);
+                std::process::exit(1);
+            })
+        };
+        let differences = parse(&s, "A").diff(&parse(&other, "B"));
+        if differences.is_empty() {
+            println!("The messages are identical");
+            return;
+        }
+        println!("{:<9} | {:<40} | Message B", "", "Message A");
+        for difference in differences {
+            let left = difference.left.unwrap_or_else(|| "(absent)".to_string());
+            let right = difference.right.unwrap_or_else(|| "(absent)".to_string());
+            println!("{:<9} | {:<40} | {}", difference.item, left, right);
+        }
+        return;
     }
-}
 
+    let format_options = FormatOptions { name_width: args.name_width, show_raw: args.raw, filter: args.filter, show_offsets: args.offsets, printable: !args.control_chars, bitmap_format: args.bitmap_format, ..Default::default() };
+    if (args.ascii || args.bcd) && !args.quiet {
+        let fields: Vec<String> = FieldCatalog::default().numeric_fields().iter().map(|bit| bit.to_string()).collect();
+        println!("Numeric Encoding: {} (fields {})", if args.ascii { "ASCII" } else { "BCD" }, fields.join(", "));
+    }
+    let results = if args.auto_header {
+        vec![parse_iso8583_auto(&s, &mode, None).map(|(result, _)| result)]
+    } else {
+        parse_iso8583_stream(&s, args.including_header_length, &mode, None)
+    };
+    let count = results.len();
+    let mut parsed = Vec::new();
+    for (i, result) in results.into_iter().enumerate() {
+        if count > 1 && !args.quiet {
+            println!("Message {}:", i + 1);
+        }
+        match result {
+            Ok(result) if args.quiet => {
+                println!("{}", quiet_line(&result));
+                parsed.push(result);
+            }
+            Ok(result) => {
+                let report = result.format(&format_options);
+                print!("{}", if color { colorize_report(&report) } else { report });
+                parsed.push(result);
+            }
+            Err(e) => eprintln!("{}", paint(&format!("Error: {}", e), RED, color)),
+        }
+    }
+    let errors = count - parsed.len();
+    if count > 1 && !args.quiet {
+        print!("{}", BatchSummary { errors, ..summarize(&parsed) });
+    }
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,103 @@
+//! # Server Module
+//!
+//! A TCP server parsing ISO8583 messages read off a socket.
+//!
+//! Messages are framed with a 2-byte binary message length indicator (MLI) holding the
+//! number of bytes that follow. Each parsed message is answered with its formatted
+//! report, framed the same way.
+
+use crate::{parse_iso8583_bytes, LengthHeaderKind, Mode};
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// Listen on `addr` and parse every message received, answering with the formatted result.
+///
+/// When `including_header` is true the message is expected to start with a header after
+/// the MLI, and is parsed the same way as `parse_iso8583` with `including_header_length`.
+pub fn serve(addr: &str, including_header: bool, mode: &Mode) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let mode = mode.clone();
+        thread::spawn(move || {
+            let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+            if let Err(e) = handle_connection(stream, including_header, &mode) {
+                eprintln!("Connection {} failed: {}", peer, e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Parse the messages of a single connection until it is closed by the peer.
+pub fn handle_connection<S: Read + Write>(mut stream: S, including_header: bool, mode: &Mode) -> io::Result<()> {
+    // The message is handed over with the binary MLI it was framed with on the socket, which
+    // leaves no room for an LRC
+    let mode = Mode { length_header: LengthHeaderKind::BinaryHex, lrc: false, ..mode.clone() };
+    while let Some(message) = read_message(&mut stream)? {
+        let response = if including_header {
+            let mut framed = (message.len() as u16).to_be_bytes().to_vec();
+            framed.extend_from_slice(&message);
+            parse_iso8583_bytes(&framed, true, &mode, None)
+        } else {
+            parse_iso8583_bytes(&message, false, &mode, None)
+        };
+        let response = match response {
+            Ok(result) => result.to_string(),
+            Err(e) => format!("Error: {}\n", e),
+        };
+        write_message(&mut stream, response.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read a message framed with a 2-byte MLI, returning `None` when the stream is closed.
+fn read_message<R: Read>(stream: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut mli = [0u8; 2];
+    match stream.read_exact(&mut mli) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut message = vec![0u8; u16::from_be_bytes(mli) as usize];
+    stream.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Write a message framed with a 2-byte MLI.
+fn write_message<W: Write>(stream: &mut W, message: &[u8]) -> io::Result<()> {
+    let length = u16::try_from(message.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message is too long"))?;
+    stream.write_all(&length.to_be_bytes())?;
+    stream.write_all(message)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_handle_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle_connection(stream, false, &Mode::default()).unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        write_message(&mut client, &[0x01, 0x00, 0x20, 0, 0, 0, 0, 0, 0, 0, 0x93, 0, 0]).unwrap();
+        let response = String::from_utf8(read_message(&mut client).unwrap().unwrap()).unwrap();
+        assert!(response.starts_with("MTI: 0100"));
+        assert!(response.contains("Process Code              | 930000"));
+
+        write_message(&mut client, &[0x01, 0x00]).unwrap();
+        let response = String::from_utf8(read_message(&mut client).unwrap().unwrap()).unwrap();
+        assert!(response.starts_with("Error: "));
+
+        drop(client);
+        server.join().unwrap();
+    }
+}
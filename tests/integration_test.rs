@@ -1,4 +1,15 @@
-use iso8583_parser::{StringManipulation, positions_of_set_bits};
+#[cfg(feature = "serde")]
+use iso8583_parser::BatchSummary;
+use iso8583_parser::{
+    bits_to_bitmap_hex, compute_lrc, describe_network_mgmt, implemented_fields, parse_bitmap,
+    parse_iso8583, parse_iso8583_auto, parse_iso8583_bytes, parse_iso8583_stream,
+    parse_original_data_elements, positions_of_set_bits, positions_of_set_bits_bytes,
+    response_category, summarize, with_length_header, BitmapFormat, Encoding, FieldCatalog,
+    FieldSpec, FormatOptions, HexCase, InputFormat, Iso8583Builder, Iso8583Error, LengthCheck,
+    LengthEncoding, LengthHeaderKind, LengthType, MacAlgo, Mode, NumericEncoding, PrivateTlv,
+    PrivateTlvFormat, ResponseCategory, StreamParser, StringManipulation, SubElements,
+    TlvErrorPolicy, TlvLengthEncoding, TransmissionDateTime, TypedMessage, EXAMPLE_MESSAGES, LTV,
+};
 
 #[cfg(test)]
 mod tests {
@@ -10,19 +21,1150 @@ mod tests {
         "00A8600008000002003038058020C1920100000000000000000100275516040011250021000800388362143741176336D28112011861000000000F303131343430323130313132393633302020202020202000321101313131313131313131311002312E312E312E312E31020330022231021532333634000000000000000000335F2A02036482020800950580000000009A032311259C01009F02060000000000011111111111111111";
 
         let mut s = test_message.to_string();
-        assert_eq!(s.get_slice_until(4), "00A8");
-        assert_eq!(s.get_slice_until(10), "6000080000");
-        let mti = s.get_slice_until(4);
+        assert_eq!(s.get_slice_until(4).unwrap(), "00A8");
+        assert_eq!(s.get_slice_until(10).unwrap(), "6000080000");
+        let mti = s.get_slice_until(4).unwrap();
         assert_eq!(mti, "0200");
 
-        let bitmap: Vec<u32> = positions_of_set_bits(u64::from_str_radix(&s.get_slice_until(16), 16).expect("Unable to get the process code"));
+        let bitmap: Vec<u32> = positions_of_set_bits(u64::from_str_radix(&s.get_slice_until(16).unwrap(), 16).expect("Unable to get the process code"));
         assert_eq!(bitmap, vec![3, 4, 11, 12, 13, 22, 24, 25, 35, 41, 42, 48, 49, 52, 55, 64]);
 
-        assert_eq!(s.get_slice_until(6), "000000");
-        assert_eq!(s.get_slice_until(12), "000000000001");
-        assert_eq!(s.get_slice_until(6), "002755");
-        assert_eq!(s.get_slice_until(6), "160400");
+        assert_eq!(s.get_slice_until(6).unwrap(), "000000");
+        assert_eq!(s.get_slice_until(12).unwrap(), "000000000001");
+        assert_eq!(s.get_slice_until(6).unwrap(), "002755");
+        assert_eq!(s.get_slice_until(6).unwrap(), "160400");
 
     }
+
+    #[test]
+    fn test_parse_iso8583_default_catalog() {
+        let mode = Mode::default();
+        let result = parse_iso8583("01002000000000000000930000", false, &mode, None).unwrap();
+        assert_eq!(result.mti, "0100");
+        assert_eq!(result.bitmap, vec![3]);
+        assert_eq!(result.fields.len(), 1);
+        assert_eq!(result.fields[0].number, 3);
+        assert_eq!(result.fields[0].name, "Process Code");
+        assert_eq!(result.fields[0].length, 6);
+        assert_eq!(result.fields[0].value, "930000");
+        assert_eq!(result.fields[0].details, vec!["\tTransaction Type: 93 | From Account: Default | To Account: Default"]);
+        assert!(result.unparsed.is_empty());
+    }
+
+    #[test]
+    fn test_parse_iso8583_custom_catalog() {
+        let mode = Mode::default();
+        let mut catalog = FieldCatalog::default();
+        catalog.insert(8, FieldSpec::new("Custom", LengthType::LLVar, 99, Encoding::Binary));
+
+        // Field 8 is not part of the default catalog
+        assert_eq!(parse_iso8583("01000100000000000000023132", false, &mode, None).unwrap_err(), Iso8583Error::UnimplementedField(8));
+
+        let result = parse_iso8583("01000100000000000000023132", false, &mode, Some(&catalog)).unwrap();
+        assert_eq!(result.bitmap, vec![8]);
+        assert_eq!(result.fields[0].to_string(), "Field   8 | Length:   4| Custom                    | 3132\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_parse_iso8583_json() {
+        let mode = Mode::default();
+        let json = iso8583_parser::parse_iso8583_json("01002000000000000000930000", false, &mode, None).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["mti"], "0100");
+        assert_eq!(value["fields"][0]["number"], 3);
+        assert_eq!(value["fields"][0]["name"], "Process Code");
+        assert_eq!(value["fields"][0]["length"], 6);
+        assert_eq!(value["fields"][0]["value"], "930000");
+    }
+
+    #[test]
+    fn test_builder_round_trip() {
+        let values = [
+            (2, "4111111111111111"),
+            (3, "000000"),
+            (4, "000000001000"),
+            (11, "123456"),
+            (41, "TERM0001"),
+            (48, "1101313131"),
+            (62, "private data"),
+            (70, "0301"),
+            (122, "more data"),
+        ];
+        let mut builder = Iso8583Builder::new();
+        builder.set_mti("0200");
+        values.iter().for_each(|&(bit, value)| { builder.set_field(bit, value); });
+        let message = builder.build().unwrap();
+        assert_eq!(&message[..4], "0200");
+        assert_eq!(&message[4..36], "F0200000008100040400000000000040");
+
+        let mode = Mode::default();
+        let result = parse_iso8583(&message, false, &mode, None).unwrap();
+        assert_eq!(result.bitmap, values.iter().map(|&(bit, _)| bit).collect::<Vec<u32>>());
+        for (field, &(bit, value)) in result.fields.iter().zip(values.iter()) {
+            assert_eq!(field.number, bit);
+            assert_eq!(field.value, value);
+        }
+        assert!(result.unparsed.is_empty());
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        assert!(Iso8583Builder::new().set_mti("0200").set_field(3, "12345").build().is_err());
+        assert!(Iso8583Builder::new().set_mti("0200").set_field(2, "41111111111111111111").build().is_err());
+        assert!(Iso8583Builder::new().set_mti("0200").set_field(8, "00").build().is_err());
+        assert!(Iso8583Builder::new().set_mti("02").set_field(3, "000000").build().is_err());
+    }
+
+    #[test]
+    fn test_parse_tertiary_bitmap() {
+        let mode = Mode::default();
+        let message = "0200C0000000000000008000000000000000400000000000000006123456000441424344";
+        let result = parse_iso8583(message, false, &mode, None).unwrap();
+        assert_eq!(result.bitmap, vec![2, 130]);
+        assert_eq!(result.fields[0].value, "123456");
+        assert_eq!(result.fields[1].number, 130);
+        assert_eq!(result.fields[1].value, "41424344");
+        assert!(result.unparsed.is_empty());
+
+        let built = Iso8583Builder::new().set_mti("0200").set_field(2, "123456").set_field(130, "41424344").build().unwrap();
+        assert_eq!(built, message);
+    }
+
+    #[test]
+    fn test_parse_iso8583_bytes() {
+        let mode = Mode::default();
+        let data = [0x01, 0x00, 0x20, 0, 0, 0, 0, 0, 0, 0, 0x93, 0, 0];
+        let result = parse_iso8583_bytes(&data, false, &mode, None).unwrap();
+        assert_eq!(result.mti, "0100");
+        assert_eq!(result.fields[0].value, "930000");
+
+        let result = parse_iso8583("\"0100 2000000000000000 930000\"", false, &mode, None).unwrap();
+        assert_eq!(result.fields[0].value, "930000");
+    }
+
+    #[test]
+    fn test_parse_ebcdic_terminal_id() {
+        let mode = Mode::default();
+        let mut catalog = FieldCatalog::default();
+        catalog.insert(41, FieldSpec::new("Terminal", LengthType::Fixed, 8, Encoding::Ebcdic));
+        let message = "08000000000000800000E3C5D9D4F0F0F0F1";
+        let result = parse_iso8583(message, false, &mode, Some(&catalog)).unwrap();
+        assert_eq!(result.fields[0].value, "TERM0001");
+
+        let built = Iso8583Builder::with_catalog(catalog).set_mti("0800").set_field(41, "TERM0001").build().unwrap();
+        assert_eq!(built, message);
+    }
+
+    #[test]
+    fn test_display_parser_result() {
+        let mode = Mode::default();
+        let result = parse_iso8583("0012600008000001002000000000000000930000", true, &mode, None).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "Lentgh Of Message: 36\n\
+             Header: 6000080000\n\
+             MTI: 0100 (1987 / Authorization / Request / Acquirer)\n\
+             First Bit Map: [3]\n\
+             Field   3 | Length:   6| Process Code              | 930000\n\
+             \tTransaction Type: 93 | From Account: Default | To Account: Default\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_iso8583_stream() {
+        let mode = Mode::default();
+        let stream = "0012600008000001002000000000000000930000\
+                      0012600008000001102000000000000000930000\
+                      00";
+        let results = parse_iso8583_stream(stream, true, &mode, None);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().mti, "0100");
+        assert_eq!(results[1].as_ref().unwrap().mti, "0110");
+
+        // The second message is cut
+        let results = parse_iso8583_stream("00126000080000010020000000000000009300000012600008", true, &mode, None);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+
+        let results = parse_iso8583_stream("01002000000000000000930000", false, &mode, None);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+    }
+
+    #[test]
+    fn test_format_options() {
+        let mode = Mode::default();
+        let result = parse_iso8583("01002000000000000000930000", false, &mode, None).unwrap();
+        let options = FormatOptions {
+            name_width: 35,
+            separator: ':',
+            show_raw: false,
+            filter: None,
+            show_offsets: false,
+            printable: true,
+            bitmap_format: BitmapFormat::Positions,
+        };
+        assert_eq!(
+            result.fields[0].format(&options),
+            "Field   3 : Length:   6: Process Code                        : 930000\n\
+             \tTransaction Type: 93 | From Account: Default | To Account: Default\n"
+        );
+        assert_eq!(result.format(&FormatOptions::default()), result.to_string());
+    }
+
+    #[test]
+    fn test_format_raw_hex() {
+        let mode = Mode::default();
+        let result = parse_iso8583("010000000000008000003132333435363738", false, &mode, None).unwrap();
+        let field = &result.fields[0];
+        assert_eq!(field.raw.as_deref(), Some("3132333435363738"));
+        let options = FormatOptions { show_raw: true, ..Default::default() };
+        assert!(field.format(&options).ends_with("| 3132333435363738 -> 12345678\n"));
+        assert!(field.to_string().ends_with("| 12345678\n"));
+    }
+
+    #[test]
+    fn test_printable_values() {
+        let mut hex = "3132003435363738".to_string();
+        assert_eq!(hex.hex_to_ascii_printable().unwrap(), "12.45678");
+        let mut hex = "41FF7F20".to_string();
+        assert_eq!(hex.hex_to_ascii_printable().unwrap(), "A.. ");
+
+        let mode = Mode::default();
+        let result = parse_iso8583("010000000000008000003132003435363738", false, &mode, None).unwrap();
+        let field = &result.fields[0];
+        assert_eq!(field.value, "12\u{0}45678");
+        assert!(field.to_string().ends_with("| 12.45678\n"));
+        let options = FormatOptions { show_raw: true, ..Default::default() };
+        assert!(field.format(&options).ends_with("| 3132003435363738 -> 12.45678\n"));
+        let options = FormatOptions { printable: false, ..Default::default() };
+        assert!(field.format(&options).ends_with("| 12\u{0}45678\n"));
+
+        // The text of a value and its raw bytes follow the same rule
+        let result = parse_iso8583("0100000000000080000041FF7F2041424344", false, &mode, None).unwrap();
+        let options = FormatOptions { show_raw: true, ..Default::default() };
+        assert!(result.fields[0].to_string().ends_with("| A.. ABCD\n"));
+        assert!(result.fields[0].format(&options).ends_with("| 41FF7F2041424344 -> A.. ABCD\n"));
+    }
+
+    #[test]
+    fn test_mac() {
+        let mode = Mode::default();
+        let key = hex::decode("0123456789ABCDEFFEDCBA9876543210").unwrap();
+        let result = parse_iso8583("020020000000000000010000001334239911A6FD57", false, &mode, None).unwrap();
+        assert_eq!(result.mac, Some(hex::decode("1334239911A6FD57").unwrap()));
+        assert_eq!(result.verify_mac(&key, MacAlgo::Retail), Some(true));
+        assert_eq!(result.verify_mac(&key[..8], MacAlgo::Retail), None);
+
+        let tampered = parse_iso8583("020020000000000000010000011334239911A6FD57", false, &mode, None).unwrap();
+        assert_eq!(tampered.verify_mac(&key, MacAlgo::Retail), Some(false));
+
+        let no_mac = parse_iso8583("01002000000000000000930000", false, &mode, None).unwrap();
+        assert_eq!(no_mac.mac, None);
+        assert_eq!(no_mac.verify_mac(&key, MacAlgo::Retail), None);
+
+        // The bytes the MAC covers are not serialized
+        #[cfg(feature = "serde")]
+        {
+            let json = iso8583_parser::parse_iso8583_json("020020000000000000010000001334239911A6FD57", false, &mode, None).unwrap();
+            let deserialized: iso8583_parser::ParserResult = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized.mac, result.mac);
+            assert_eq!(deserialized.verify_mac(&key, MacAlgo::Retail), None);
+        }
+
+        // A catalog may give the MAC field a length other than 8 bytes
+        let mut catalog = FieldCatalog::default();
+        catalog.insert(64, FieldSpec::new("MAC", LengthType::Fixed, 4, Encoding::Binary));
+        assert_eq!(
+            parse_iso8583("0200200000000000000100000013342399", false, &mode, Some(&catalog)).unwrap_err(),
+            Iso8583Error::InvalidField { bit: 64, reason: "MAC must be 8 bytes but is 8 characters long".to_string() }
+        );
+
+        assert_eq!(
+            parse_iso8583("020020000000000000010000001334239911", false, &mode, None).unwrap_err(),
+            Iso8583Error::TruncatedField { bit: 64, needed: 16, available: 10 }
+        );
+    }
+
+    #[test]
+    fn test_lllvar_at_end_of_message() {
+        let mode = Mode::default();
+        let result = parse_iso8583("010000000000000100000003ABCDEF", false, &mode, None).unwrap();
+        assert_eq!(result.fields[0].number, 48);
+        assert_eq!(result.fields[0].value, "ABCDEF");
+        assert_eq!(result.unparsed, "");
+
+        assert_eq!(
+            parse_iso8583("010000000000000100000004ABCDEF", false, &mode, None).unwrap_err(),
+            Iso8583Error::TruncatedField { bit: 48, needed: 8, available: 6 }
+        );
+        assert_eq!(
+            parse_iso8583("0100000000000001000000", false, &mode, None).unwrap_err(),
+            Iso8583Error::TruncatedField { bit: 48, needed: 4, available: 2 }
+        );
+    }
+
+    #[test]
+    fn test_ascii_length_prefixes() {
+        let mode = Mode { length_encoding: LengthEncoding::Ascii, ..Default::default() };
+        let result = parse_iso8583("010040000000000100003136411111111111111130303341424344", false, &mode, None).unwrap();
+        assert_eq!(result.fields[0].value, "4111111111111111");
+        assert_eq!(result.fields[1].value, "414243");
+        assert_eq!(result.unparsed, "44");
+
+        let bcd = parse_iso8583("01004000000000010000164111111111111111000341424344", false, &Mode::default(), None).unwrap();
+        let values = |result: &iso8583_parser::ParserResult| result.iter().map(|field| (field.number, field.value.clone())).collect::<Vec<_>>();
+        assert_eq!(values(&bcd), values(&result));
+    }
+
+    #[test]
+    fn test_auto_detect_private() {
+        let message = "0100000000000001000000073031303341424344";
+        let auto = Mode { auto_detect_private: true, ..Default::default() };
+        let result = parse_iso8583(message, false, &auto, None).unwrap();
+        assert_eq!(result.fields[0].details.len(), 1);
+        assert!(parse_iso8583(message, false, &Mode::default(), None).unwrap().fields[0].details.is_empty());
+
+        let not_tlv = parse_iso8583("010000000000000100000002FFFF", false, &auto, None).unwrap();
+        assert_eq!(not_tlv.fields[0].value, "FFFF");
+        assert!(not_tlv.fields[0].details.is_empty());
+    }
+
+    #[test]
+    fn test_empty_secondary_bitmap_warning() {
+        let mode = Mode::default();
+        let result = parse_iso8583("0100A0000000000000000000000000000000930000", false, &mode, None).unwrap();
+        assert_eq!(result.bitmap, vec![3]);
+        assert_eq!(result.warnings, vec!["Bit 1 is set but the secondary bitmap is empty"]);
+        assert!(result.to_string().ends_with("Warnings:\n\tBit 1 is set but the secondary bitmap is empty\n"));
+
+        let result = parse_iso8583("01002000000000000000930000", false, &mode, None).unwrap();
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_warnings() {
+        let mode = Mode::default();
+        let result = parse_iso8583("01004000000000008000164111111111111112393939FF", false, &mode, None).unwrap();
+        assert_eq!(result.fields[0].warnings, vec!["PAN fails the Luhn check"]);
+        assert_eq!(
+            result.warnings,
+            vec!["Field 2: PAN fails the Luhn check", "Field 49: unknown currency code 999", "2 characters were left unparsed"]
+        );
+
+        let result = parse_iso8583("01004000000000008000164111111111111111333634", false, &mode, None).unwrap();
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_implemented_fields() {
+        let fields = implemented_fields();
+        assert!(fields.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(fields.contains(&2) && fields.contains(&55) && fields.contains(&128));
+        assert!(!fields.contains(&1) && !fields.contains(&65));
+        assert_eq!(fields, FieldCatalog::default().bits());
+    }
+
+    #[test]
+    fn test_lenient_unimplemented_field() {
+        let message = "01002100000000000000930000AABBCC";
+        assert_eq!(parse_iso8583(message, false, &Mode::default(), None).unwrap_err(), Iso8583Error::UnimplementedField(8));
+
+        let mode = Mode { lenient: true, ..Default::default() };
+        let result = parse_iso8583(message, false, &mode, None).unwrap();
+        assert_eq!(result.fields.len(), 2);
+        assert_eq!(result.fields[0].value, "930000");
+        assert_eq!(result.fields[1].number, 8);
+        assert_eq!(result.fields[1].name, "(unimplemented, raw tail)");
+        assert_eq!(result.fields[1].value, "AABBCC");
+        assert_eq!(result.unparsed, "AABBCC");
+        assert!(result.warnings.contains(&"Field 8: field is not implemented".to_string()));
+
+        assert!(!result.warnings.iter().any(|warning| warning.starts_with("Fields signalled")));
+
+        let result = parse_iso8583("01002120000000000000930000AABBCC", false, &mode, None).unwrap();
+        assert_eq!(result.bitmap, vec![3, 8, 11]);
+        assert_eq!(result.fields.len(), 2);
+        assert!(result.warnings.contains(&"Fields signalled by the bitmap but not parsed: 11".to_string()));
+    }
+
+    #[test]
+    fn test_field_accessors() {
+        let result = parse_iso8583("01007000000000000000164111111111111111930000000000000100", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(3).unwrap().value, "930000");
+        assert!(result.has_field(4));
+        assert!(!result.has_field(39));
+        assert_eq!(result.iter().map(|field| field.number).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!((&result).into_iter().count(), 3);
+        let numbers: Vec<u32> = result.into_iter().map(|field| field.number).collect();
+        assert_eq!(numbers, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_catalog_by_mti_version() {
+        let mode = Mode::default();
+        let result = parse_iso8583("0110000000000200000030300000", false, &mode, None).unwrap();
+        assert_eq!(result.field(39).unwrap().value, "3030");
+        assert_eq!(result.unparsed, "0000");
+
+        let result = parse_iso8583("1110000000000200000030303000", false, &mode, None).unwrap();
+        assert_eq!(result.field(39).unwrap().name, "Action Code");
+        assert_eq!(result.field(39).unwrap().value, "000");
+        assert_eq!(result.unparsed, "00");
+
+        let catalog = FieldCatalog::iso1987();
+        let result = parse_iso8583("1110000000000200000030303000", false, &mode, Some(&catalog)).unwrap();
+        assert_eq!(result.field(39).unwrap().value, "3030");
+
+        let message = Iso8583Builder::new().set_mti("1100").set_field(12, "231125143000").build().unwrap();
+        let result = parse_iso8583(&message, false, &mode, None).unwrap();
+        assert_eq!(result.field(12).unwrap().details, vec!["\t23-11-25 14:30:00"]);
+    }
+
+    #[test]
+    fn test_format_filter() {
+        let result = parse_iso8583("01007000000000000000164111111111111111930000000000000100", false, &Mode::default(), None).unwrap();
+        assert!(result.field(3).unwrap().matches("process"));
+        assert!(result.field(4).unwrap().matches("4"));
+        assert!(!result.field(4).unwrap().matches("process"));
+
+        let options = FormatOptions { filter: Some("AMOUNT".to_string()), ..Default::default() };
+        let output = result.format(&options);
+        assert!(output.contains("Field   4 "));
+        assert!(!output.contains("Field   2 ") && !output.contains("Field   3 "));
+    }
+
+    #[test]
+    fn test_field_offsets() {
+        let result = parse_iso8583("01007000000000000000164111111111111111930000000000000100", false, &Mode::default(), None).unwrap();
+        let offsets: Vec<(usize, usize)> = result.iter().map(|field| (field.start_offset, field.end_offset)).collect();
+        assert_eq!(offsets, vec![(20, 38), (38, 44), (44, 56)]);
+        let byte_offsets: Vec<(usize, usize)> = result.iter().map(|field| (field.offset(), field.len_consumed())).collect();
+        assert_eq!(byte_offsets, vec![(10, 9), (19, 3), (22, 6)]);
+
+        let options = FormatOptions { show_offsets: true, ..Default::default() };
+        assert!(result.field(3).unwrap().format(&options).starts_with("Field   3 | Length:   6| Offset:   38..  44| Process Code"));
+    }
+
+    #[test]
+    fn test_bits_to_bitmap_hex() {
+        for bitmap in ["0000000000000000", "3038058020C19201", "8000000000000000", "FFFFFFFFFFFFFFFF", "7234054128C28805"] {
+            let positions = positions_of_set_bits(u64::from_str_radix(bitmap, 16).unwrap());
+            assert_eq!(bits_to_bitmap_hex(&positions), bitmap);
+        }
+        assert_eq!(bits_to_bitmap_hex(&[3, 70]), "A0000000000000000400000000000000");
+        assert_eq!(bits_to_bitmap_hex(&[130]), "800000000000000080000000000000004000000000000000");
+    }
+
+    #[test]
+    fn test_positions_of_set_bits_bytes() {
+        assert_eq!(positions_of_set_bits_bytes(&[]), Vec::<u32>::new());
+        assert_eq!(positions_of_set_bits_bytes(&[0x80, 0x01]), vec![1, 16]);
+        let bitmap = hex::decode("B2200000001000000000000000000001").unwrap();
+        assert_eq!(positions_of_set_bits_bytes(&bitmap), vec![1, 3, 4, 7, 11, 44, 128]);
+        assert_eq!(positions_of_set_bits(0x3038058020C19201), positions_of_set_bits_bytes(&hex::decode("3038058020C19201").unwrap()));
+    }
+
+    #[test]
+    fn test_field_longer_than_max_length() {
+        let message = format!("0100400000000000000040{}", "4".repeat(40));
+        let error = parse_iso8583(&message, false, &Mode::default(), None).unwrap_err();
+        assert_eq!(error, Iso8583Error::FieldTooLong { bit: 2, length: 40, max_length: 19 });
+        assert_eq!(error.to_string(), "Field 2 has a length of 40 but its maximum length is 19");
+    }
+
+    #[test]
+    fn test_odd_length_pan() {
+        let mode = Mode::default();
+        let result = parse_iso8583("01006000000000000000194111111111111111111F930000", false, &mode, None).unwrap();
+        assert_eq!(result.field(2).unwrap().value, "4111111111111111111");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let result = parse_iso8583("0100600000000000000019041111111111111111119300000", false, &mode, None);
+        assert!(result.is_err());
+        // Variable length values are padded at their end, even with 0 and when they start with 0
+        let result = parse_iso8583("010060000000000000001941111111111111111110930000", false, &mode, None).unwrap();
+        assert_eq!(result.field(2).unwrap().value, "4111111111111111111");
+        let result = parse_iso8583("0100000000008000000005012340", false, &mode, None).unwrap();
+        assert_eq!(result.field(33).unwrap().value, "01234");
+
+        let mut catalog = FieldCatalog::default();
+        catalog.insert(2, FieldSpec::new("PAN", LengthType::LLVar, 19, Encoding::Ascii));
+        let message = format!("0100600000000000000019{}930000", hex::encode("4111111111111111111"));
+        let result = parse_iso8583(&message, false, &mode, Some(&catalog)).unwrap();
+        assert_eq!(result.field(2).unwrap().value, "4111111111111111111");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let built = Iso8583Builder::new().set_mti("0100").set_field(2, "4111111111111111111").set_field(19, "364").build().unwrap();
+        assert_eq!(built, "01004000200000000000194111111111111111111F0364");
+    }
+    #[test]
+    fn test_ascii_numeric_encoding() {
+        let mode = Mode { numeric_encoding: NumericEncoding::Ascii, ..Default::default() };
+        let message = format!("0100600000000000000016{}{}", hex::encode("4111111111111111"), hex::encode("930000"));
+        let result = parse_iso8583(&message, false, &mode, None).unwrap();
+        assert_eq!(result.field(2).unwrap().value, "4111111111111111");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+        assert_eq!(result.unparsed, "");
+
+        let bcd = parse_iso8583("01006000000000000000164111111111111111930000", false, &Mode::default(), None).unwrap();
+        assert_eq!(bcd.field(3).unwrap().value, "930000");
+        assert_eq!(&FieldCatalog::default().numeric_fields()[..3], &[2, 3, 4]);
+    }
+    #[test]
+    fn test_ascii_decimal_length_header() {
+        let mode = Mode { length_header: LengthHeaderKind::AsciiDecimal, ..Default::default() };
+        let result = parse_iso8583("303031386000080000010020000000000000009300003", true, &mode, None);
+        assert!(result.is_err());
+        let result = parse_iso8583("30303138600008000001002000000000000000930000", true, &mode, None).unwrap();
+        assert_eq!(result.message_length, Some(36));
+        assert_eq!(result.header.as_deref(), Some("6000080000"));
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let result = parse_iso8583("30303230600008000001002000000000000000930000", true, &mode, None);
+        assert_eq!(result.unwrap_err(), Iso8583Error::LengthMismatch { expected: 40, actual: 36 });
+        let result = parse_iso8583("30304131600008000001002000000000000000930000", true, &mode, None);
+        assert_eq!(result.unwrap_err(), Iso8583Error::InvalidLengthHeader("30304131".to_string()));
+
+        let results = parse_iso8583_stream(&"30303138600008000001002000000000000000930000".repeat(2), true, &mode, None);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.is_ok()));
+    }
+    #[test]
+    fn test_header_len() {
+        let mode = Mode { header_len: 6, ..Default::default() };
+        let result = parse_iso8583("0010600008010020000000000000009300", true, &mode, None);
+        assert!(result.is_err());
+        let result = parse_iso8583("001060000801002000000000000000930000", true, &mode, None).unwrap();
+        assert_eq!(result.header.as_deref(), Some("600008"));
+        assert_eq!(result.mti, "0100");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let mode = Mode { header_len: 0, ..Default::default() };
+        let result = parse_iso8583("000D01002000000000000000930000", true, &mode, None).unwrap();
+        assert_eq!(result.header.as_deref(), Some(""));
+        assert_eq!(result.mti, "0100");
+
+        let mode = Mode { header_len: 12, ..Default::default() };
+        let result = parse_iso8583("00056000080000", true, &mode, None);
+        assert_eq!(result.unwrap_err(), Iso8583Error::HeaderTooLong { header_len: 12, available: 10 });
+    }
+    #[test]
+    fn test_emv_tags() {
+        let result = parse_iso8583("0100000000000000020000219F2608D547079427A420B7950580000000009C0100", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.emv_tags.len(), 3);
+        let arqc = result.emv_tag("9f26").unwrap();
+        assert_eq!(arqc.name, "Application Cryptogram");
+        assert_eq!(arqc.length, 8);
+        assert_eq!(arqc.value, vec![0xD5, 0x47, 0x07, 0x94, 0x27, 0xA4, 0x20, 0xB7]);
+        assert_eq!(result.emv_tag("95").unwrap().value, vec![0x80, 0, 0, 0, 0]);
+        assert!(result.emv_tag("9F27").is_none());
+        assert!(result.fields[0].details.contains(&"\tTVR: Offline data authentication was not performed".to_string()));
+
+        let result = parse_iso8583("01002000000000000000930000", false, &Mode::default(), None).unwrap();
+        assert!(result.emv_tags.is_empty());
+    }
+    #[test]
+    fn test_diff() {
+        let mode = Mode::default();
+        let request = parse_iso8583("01006000000000000000164111111111111111930000", false, &mode, None).unwrap();
+        let response = parse_iso8583("011020000000020000009300003030", false, &mode, None).unwrap();
+        assert!(request.diff(&request).is_empty());
+        let differences = request.diff(&response);
+        let items: Vec<_> = differences.iter().map(|difference| difference.item.as_str()).collect();
+        assert_eq!(items, ["MTI", "Bitmap", "Field 2", "Field 39"]);
+        assert_eq!(differences[2].left.as_deref(), Some("4111111111111111"));
+        assert_eq!(differences[2].right, None);
+        assert_eq!(differences[3].left, None);
+        assert_eq!(differences[3].right.as_deref(), Some("3030"));
+    }
+    #[test]
+    fn test_parse_iso8583_auto() {
+        let mode = Mode::default();
+        let (result, with_header) = parse_iso8583_auto("0012600008000001002000000000000000930000", &mode, None).unwrap();
+        assert!(with_header);
+        assert_eq!(result.header.as_deref(), Some("6000080000"));
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let (result, with_header) = parse_iso8583_auto("01002000000000000000930000", &mode, None).unwrap();
+        assert!(!with_header);
+        assert_eq!(result.mti, "0100");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        // The length matches but the fields after the header do not
+        let (result, with_header) = parse_iso8583_auto("0012200000000000000093000000000000000000", &mode, None).unwrap();
+        assert!(!with_header);
+        assert_eq!(result.mti, "0012");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let result = parse_iso8583_auto("0100200000000000000093", &mode, None);
+        assert!(matches!(result, Err(Iso8583Error::TruncatedField { bit: 3, .. })));
+    }
+    #[test]
+    fn test_non_numeric_mti() {
+        let result = parse_iso8583("00A22000000000000000930000", false, &Mode::default(), None);
+        assert_eq!(result.unwrap_err(), Iso8583Error::InvalidMti("00A2".to_string()));
+
+        let mode = Mode { lenient: true, ..Default::default() };
+        let result = parse_iso8583("00A22000000000000000930000", false, &mode, None).unwrap();
+        assert_eq!(result.decoded_mti, None);
+        assert_eq!(result.warnings, vec!["MTI \"00A2\" is not made of 4 digits".to_string()]);
+        assert_eq!(result.field(3).unwrap().value, "930000");
+    }
+    #[test]
+    fn test_field_names() {
+        let mut mode = Mode::default();
+        mode.field_names.insert(3, "Transaction Kind".to_string());
+        let result = parse_iso8583("01006000000000000000164111111111111111930000", false, &mode, None).unwrap();
+        assert_eq!(result.field(2).unwrap().name, "PAN");
+        assert_eq!(result.field(3).unwrap().name, "Transaction Kind");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+    }
+    #[test]
+    fn test_typed_message() {
+        let message = "0200722000000A800000164111111111111111000000000000012345101412304500012331323334353637383930313230305445524D30303031";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        let typed = TypedMessage::from_iso8583(result).unwrap();
+        assert_eq!(typed.mti(), "0200");
+        assert_eq!(typed.pan(), Some("4111111111111111"));
+        assert_eq!(typed.processing_code().unwrap().transaction_type, "Purchase");
+        assert_eq!(typed.amount(), Some(12345));
+        assert_eq!(typed.transmission_date_time(), Some(TransmissionDateTime { month: 10, day: 14, hour: 12, minute: 30, second: 45 }));
+        assert_eq!(typed.stan(), Some(123));
+        assert_eq!(typed.rrn(), Some("123456789012"));
+        assert_eq!(typed.response_code(), Some("00"));
+        assert_eq!(typed.terminal_id(), Some("TERM0001"));
+        assert!(typed.field(41).is_some());
+
+        let result = parse_iso8583("01002000000000000000930000", false, &Mode::default(), None).unwrap();
+        let typed = TypedMessage::try_from(result).unwrap();
+        assert_eq!(typed.amount(), None);
+        assert_eq!(typed.pan(), None);
+
+        let result = parse_iso8583("0100100000000000000000000000123A", false, &Mode { lenient: true, ..Default::default() }, None).unwrap();
+        assert!(matches!(TypedMessage::from_iso8583(result), Err(Iso8583Error::InvalidField { bit: 4, .. })));
+    }
+    #[test]
+    fn test_track2_lengths() {
+        let mode = Mode::default();
+        for track2 in ["4111111111111111D2512101", "411111111111111D2512101", "4111111111111111D25121011234567890123", "4111111111111111D25121011234567890123F"] {
+            let padded = if track2.len() % 2 == 1 { format!("{}F", track2) } else { track2.to_string() };
+            let message = format!("01000000000020800000{:02}{}3132333435363738", track2.len(), padded);
+            let result = parse_iso8583(&message, false, &mode, None).unwrap_or_else(|e| panic!("{}: {}", track2, e));
+            let field = result.field(35).unwrap();
+            assert_eq!(field.value, track2);
+            assert_eq!(field.length, track2.len() as u32);
+            assert!(field.details[0].contains("PAN: 411111"), "{}: {:?}", track2, field.details);
+            assert_eq!(result.field(41).unwrap().value, "12345678");
+            assert_eq!(result.unparsed, "");
+        }
+    }
+    #[test]
+    fn test_parse_canonical_message() {
+        let test_message =
+        "00A8600008000002003038058020C1920100000000000000000100275516040011250021000800388362143741176336D28112011861000000000F303131343430323130313132393633302020202020202000321101313131313131313131311002312E312E312E312E31020330022231021532333634000000000000000000335F2A02036482020800950580000000009A032311259C01009F02060000000000011111111111111111";
+        let result = parse_iso8583(test_message, true, &Mode::default(), None).unwrap();
+        let values: Vec<(u32, &str)> = result.iter().map(|field| (field.number, field.value.as_str())).collect();
+        assert_eq!(values, vec![
+            (3, "000000"),
+            (4, "000000000001"),
+            (11, "002755"),
+            (12, "160400"),
+            (13, "1125"),
+            (22, "0021"),
+            (24, "0008"),
+            (25, "00"),
+            (35, "8362143741176336D28112011861000000000F"),
+            (41, "01144021"),
+            (42, "01129630       "),
+            (48, "1101313131313131313131311002312E312E312E312E31020330022231021532"),
+            (49, "364"),
+            (52, "0000000000000000"),
+            (55, "5F2A02036482020800950580000000009A032311259C01009F0206000000000001"),
+            (64, "1111111111111111"),
+        ]);
+        let track2 = result.field(35).unwrap();
+        assert_eq!(track2.length, 38);
+        assert_eq!(track2.end_offset - track2.start_offset, 40);
+        assert_eq!(result.unparsed, "");
+    }
+    #[test]
+    fn test_pin_block_and_security_control() {
+        let message = "02000000000000001801".to_string() + "0123456789ABCDEF" + "2601000000000000" + "1111111111111111";
+        let result = parse_iso8583(&message, false, &Mode::default(), None).unwrap();
+        let pin_block = result.field(52).unwrap();
+        assert_eq!(pin_block.name, "PIN Block (encrypted)");
+        assert_eq!(pin_block.value, "0123456789ABCDEF");
+        assert_eq!(pin_block.raw, None);
+        assert_eq!(pin_block.details, vec!["\tBytes: 01 23 45 67 89 AB CD EF"]);
+        assert_eq!(result.field(53).unwrap().value, "2601000000000000");
+        assert_eq!(result.field(64).unwrap().value, "1111111111111111");
+        assert_eq!(result.unparsed, "");
+    }
+    #[test]
+    fn test_reserved_fields() {
+        let message = "0100000000000000000A000231310003414243";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(61).unwrap().name, "Reserved (Private)");
+        assert_eq!(result.field(61).unwrap().value, "11");
+        assert_eq!(result.field(63).unwrap().value, "ABC");
+
+        let message = "010000000000C000000009123456789F03414243";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(33).unwrap().value, "123456789");
+        assert_eq!(result.field(34).unwrap().value, "ABC");
+        assert_eq!(result.unparsed, "");
+    }
+
+    #[test]
+    fn test_sub_elements() {
+        let message = "0100000000000001000000073031303341424344";
+        let tlv = Mode { enabled_private_tlv: true, ..Default::default() };
+        let result = parse_iso8583(message, false, &tlv, None).unwrap();
+        let expected = vec![PrivateTlv { tag: "01".to_string(), length: 3, value: "ABC".to_string() }];
+        assert_eq!(result.fields[0].sub_elements, Some(SubElements::PrivateTlv(expected)));
+        assert!(parse_iso8583(message, false, &Mode::default(), None).unwrap().fields[0].sub_elements.is_none());
+
+        let ltv = Mode { enabled_private_ltv: true, ..Default::default() };
+        let result = parse_iso8583("0100000000000001000000030201AB", false, &ltv, None).unwrap();
+        let Some(SubElements::Ltv(ltvs)) = &result.fields[0].sub_elements else { panic!("no LTV elements") };
+        assert_eq!(ltvs, &vec![LTV { length: 2, tag: 1, value: "AB".to_string() }]);
+    }
+
+    #[test]
+    fn test_pan_length_prefix() {
+        // The BCD prefix 0x16 is a length of 16 digits, not 22
+        let result = parse_iso8583("01006000000000000000164111111111111111930000", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(2).unwrap().length, 16);
+        assert_eq!(result.field(2).unwrap().value, "4111111111111111");
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        let error = parse_iso8583("010040000000000000001A4111111111111111", false, &Mode::default(), None).unwrap_err();
+        assert_eq!(error, Iso8583Error::InvalidField { bit: 2, reason: "length prefix 1A is not made of BCD digits".to_string() });
+
+        let ascii = Mode { length_encoding: LengthEncoding::Ascii, ..Default::default() };
+        let error = parse_iso8583("01004000000000000000164111111111111111", false, &ascii, None).unwrap_err();
+        assert_eq!(error, Iso8583Error::InvalidField { bit: 2, reason: "length prefix \"\\u{16}A\" is not made of ASCII digits".to_string() });
+    }
+
+    #[test]
+    fn test_summarize() {
+        let mode = Mode::default();
+        let messages = [
+            "01002000000000000000930000",
+            "021030000000020000009300000000000015003030",
+            "021030000000020000009300000000000025013035",
+        ];
+        let results: Vec<_> = messages.iter().map(|message| parse_iso8583(message, false, &mode, None).unwrap()).collect();
+        let summary = summarize(&results);
+        assert_eq!(summary.messages, 3);
+        assert_eq!(summary.errors, 0);
+        assert_eq!(summary.by_mti.get("0100"), Some(&1));
+        assert_eq!(summary.by_mti.get("0210"), Some(&2));
+        assert_eq!(summary.by_response_code.get("00"), Some(&1));
+        assert_eq!(summary.by_response_code.get("05"), Some(&1));
+        assert_eq!((summary.amounts, summary.total_amount, summary.average_amount), (2, 4001, Some(2000)));
+        assert!(summary.to_string().contains("\tAmounts: 2 | Total: 4001 | Average: 2000\n"));
+
+        let empty = summarize(&[]);
+        assert_eq!(empty.average_amount, None);
+        assert_eq!(empty.to_string(), "Summary:\n\tMessages: 0 | Errors: 0\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_summary_json() {
+        let mode = Mode::default();
+        let result = parse_iso8583("021030000000020000009300000000000015003030", false, &mode, None).unwrap();
+        let summary = BatchSummary { errors: 2, ..summarize(&[result]) };
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(value["errors"], 2);
+        assert_eq!(value["by_mti"]["0210"], 1);
+        assert_eq!(value["total_amount"], 1500);
+    }
+
+    #[test]
+    fn test_stream_parser() {
+        let mut parser = StreamParser::new(Mode { header_len: 0, ..Default::default() }, None);
+        let message = hex::decode("000D01002000000000000000930000").unwrap();
+
+        // The length header is split between two chunks
+        assert!(parser.feed(&message[..1]).is_empty());
+        assert_eq!(parser.pending_bytes(), 1);
+        assert!(parser.feed(&message[1..8]).is_empty());
+        assert_eq!(parser.pending_bytes(), 8);
+        let results = parser.feed(&message[8..]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap().field(3).unwrap().value, "930000");
+        assert_eq!(parser.pending_bytes(), 0);
+
+        let mut chunk = message.repeat(2);
+        chunk.extend_from_slice(&message[..5]);
+        let results = parser.feed(&chunk);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(parser.pending_bytes(), 5);
+        assert_eq!(parser.feed(&message[5..]).len(), 1);
+
+        let mut ascii = StreamParser::new(Mode { header_len: 0, length_header: LengthHeaderKind::AsciiDecimal, ..Default::default() }, None);
+        let results = ascii.feed(b"00A1rest");
+        assert!(matches!(results[..], [Err(Iso8583Error::InvalidLengthHeader(_))]));
+        assert_eq!(ascii.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn test_settlement_fields() {
+        let message = "080080000000000000004640040000000000010301000700000000120000000000150000";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(66).unwrap().name, "Settlement Code");
+        assert_eq!(result.field(66).unwrap().value, "1");
+        assert_eq!(result.field(70).unwrap().value, "0301");
+        assert_eq!(result.field(71).unwrap().value, "0007");
+        assert_eq!(result.field(74).unwrap().value, "0000000012");
+        assert_eq!(result.field(86).unwrap().value, "0000000000150000");
+        assert_eq!(result.unparsed, "");
+        assert!((66..=90).all(|bit| implemented_fields().contains(&bit)));
+    }
+
+    #[test]
+    fn test_network_management() {
+        assert_eq!(describe_network_mgmt("301"), Some("Echo Test"));
+        assert_eq!(describe_network_mgmt("0001"), Some("Sign-on"));
+        assert_eq!(describe_network_mgmt("002"), Some("Sign-off"));
+        assert_eq!(describe_network_mgmt("1301"), None);
+        assert_eq!(describe_network_mgmt("999"), None);
+
+        let result = parse_iso8583("0800800000000000000004000000000000000301", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.network_management(), Some("Echo Test"));
+        assert_eq!(result.field(70).unwrap().details, vec!["\tNetwork Management Code 0301: Echo Test"]);
+        assert!(result.to_string().contains("Network Management: Echo Test\nFirst Bit Map"));
+
+        let result = parse_iso8583("0200800000000000000004000000000000000301", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.network_management(), None);
+        assert!(result.field(70).unwrap().details.is_empty());
+    }
+
+    #[test]
+    fn test_length_check() {
+        let message = "00120000000000010020000000000000009300004F";
+        assert_eq!(
+            parse_iso8583(message, true, &Mode::default(), None).unwrap_err(),
+            Iso8583Error::LengthMismatch { expected: 36, actual: 38 }
+        );
+
+        let at_least = Mode { length_check: LengthCheck::AtLeast, ..Default::default() };
+        let result = parse_iso8583(message, true, &at_least, None).unwrap();
+        assert_eq!(result.field(3).unwrap().value, "930000");
+        assert_eq!(result.unparsed, "4F");
+        assert_eq!(result.warnings, vec!["2 characters follow the length declared by the length header"]);
+        let results = parse_iso8583_stream(message, true, &at_least, None);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap().unparsed, "4F");
+        assert!(parse_iso8583("0013000000000001002000000000000000930000", true, &at_least, None).is_err());
+
+        let ignore = Mode { length_check: LengthCheck::Ignore, ..Default::default() };
+        let result = parse_iso8583("0013000000000001002000000000000000930000", true, &ignore, None).unwrap();
+        assert_eq!(result.message_length, Some(38));
+        assert_eq!(result.field(3).unwrap().value, "930000");
+    }
+
+    #[test]
+    fn test_lrc() {
+        assert_eq!(compute_lrc(&[]), 0);
+        assert_eq!(compute_lrc(&[0x01, 0x02, 0x04]), 0x07);
+        assert_eq!(compute_lrc(&hex::decode("01002000000000000000930000").unwrap()), 0xB2);
+
+        let lrc = Mode { lrc: true, ..Default::default() };
+        let result = parse_iso8583("01002000000000000000930000B2", false, &lrc, None).unwrap();
+        assert_eq!(result.trailer_ok, Some(true));
+        assert_eq!(result.field(3).unwrap().value, "930000");
+        assert_eq!(result.unparsed, "");
+
+        let result = parse_iso8583("0100200000000000000093000042", false, &lrc, None).unwrap();
+        assert_eq!(result.trailer_ok, Some(false));
+        assert_eq!(result.warnings, vec!["LRC 42 does not match the computed B2"]);
+        assert_eq!(parse_iso8583("01002000000000000000930000", false, &Mode::default(), None).unwrap().trailer_ok, None);
+
+        // The length header does not count the LRC
+        let framed = Mode { lrc: true, header_len: 0, ..Default::default() };
+        let results = parse_iso8583_stream("000D01002000000000000000930000BF000D01002000000000000000930000BF", true, &framed, None);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.as_ref().unwrap().trailer_ok == Some(true)));
+    }
+
+    #[test]
+    fn test_strip_framing() {
+        let framing = Mode { strip_framing: true, ..Default::default() };
+        for message in ["020100200000000000000093000003", "020100200000000000000093000003B1", "01002000000000000000930000"] {
+            let result = parse_iso8583(message, false, &framing, None).unwrap();
+            assert_eq!(result.field(3).unwrap().value, "930000", "{}", message);
+            assert_eq!(result.unparsed, "", "{}", message);
+        }
+
+        let with_lrc = Mode { strip_framing: true, lrc: true, ..Default::default() };
+        let result = parse_iso8583("020100200000000000000093000003B1", false, &with_lrc, None).unwrap();
+        assert_eq!(result.trailer_ok, Some(true));
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        assert_eq!(
+            parse_iso8583("0201002000000000000000930000", false, &framing, None).unwrap_err(),
+            Iso8583Error::InvalidFraming("the message starts with STX but has no ETX".to_string())
+        );
+        assert!(matches!(
+            parse_iso8583("020100200000000000000093000003", false, &with_lrc, None),
+            Err(Iso8583Error::InvalidFraming(_))
+        ));
+    }
+
+    #[test]
+    fn test_with_length_header() {
+        let message = with_length_header("0000000000 01002000000000000000930000").unwrap();
+        assert_eq!(message, "0012000000000001002000000000000000930000");
+        let mode = Mode::default();
+        assert_eq!(parse_iso8583(&message, true, &mode, None).unwrap().field(3).unwrap().value, "930000");
+
+        assert_eq!(with_length_header("").unwrap(), "0000");
+        assert!(matches!(with_length_header("0100200"), Err(Iso8583Error::InvalidHex(_))));
+        assert!(matches!(with_length_header("01G0"), Err(Iso8583Error::InvalidHex(_))));
+        assert_eq!(with_length_header(&"00".repeat(65536)).unwrap_err(), Iso8583Error::MessageTooLong(65536));
+    }
+
+    #[test]
+    fn test_tlv_error_policy() {
+        let message = "0100000000000000020000029F1A";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(55).unwrap().details, vec!["Error parsing TLV: Unexpected end of data"]);
+
+        let raw = Mode { tlv_error_policy: TlvErrorPolicy::Raw, ..Default::default() };
+        let result = parse_iso8583(message, false, &raw, None).unwrap();
+        assert_eq!(result.field(55).unwrap().details, vec!["Error parsing TLV: Unexpected end of data", "\tRaw: 9F1A"]);
+
+        let fail = Mode { tlv_error_policy: TlvErrorPolicy::Fail, ..Default::default() };
+        assert_eq!(
+            parse_iso8583(message, false, &fail, None).unwrap_err(),
+            Iso8583Error::InvalidField { bit: 55, reason: "invalid EMV data: Unexpected end of data".to_string() }
+        );
+        assert!(parse_iso8583("0100000000000000020000039F1A00", false, &fail, None).is_ok());
+    }
+
+    #[test]
+    fn test_private_tlv_formats() {
+        let mut value = "1F03ABCDEF2A0112".to_string();
+        let tlvs = value.parse_private_tlv_with(&PrivateTlvFormat::binary()).unwrap();
+        assert_eq!(tlvs, vec![
+            PrivateTlv { tag: "1F".to_string(), length: 3, value: "ABCDEF".to_string() },
+            PrivateTlv { tag: "2A".to_string(), length: 1, value: "12".to_string() },
+        ]);
+
+        let format = PrivateTlvFormat { tag_bytes: 2, length_bytes: 2, length_encoding: TlvLengthEncoding::Binary, ..PrivateTlvFormat::binary() };
+        let mut value = "DF010002CAFE".to_string();
+        let tlvs = value.parse_private_tlv_with(&format).unwrap();
+        assert_eq!(tlvs, vec![PrivateTlv { tag: "DF01".to_string(), length: 2, value: "CAFE".to_string() }]);
+
+        let format = PrivateTlvFormat { length_encoding: TlvLengthEncoding::AsciiDecimal, ..PrivateTlvFormat::ascii() };
+        let mut value = format!("{}{}", hex::encode_upper("0112"), hex::encode_upper("HELLO WORLD!"));
+        assert_eq!(value.parse_private_tlv_with(&format).unwrap()[0].value, "HELLO WORLD!");
+
+        let binary = Mode { enabled_private_tlv: true, private_tlv_format: PrivateTlvFormat::binary(), ..Default::default() };
+        let result = parse_iso8583("01000000000000010000000401021234", false, &binary, None).unwrap();
+        let expected = vec![PrivateTlv { tag: "01".to_string(), length: 2, value: "1234".to_string() }];
+        assert_eq!(result.field(48).unwrap().sub_elements, Some(SubElements::PrivateTlv(expected)));
+    }
+
+    #[test]
+    fn test_parse_bitmap() {
+        assert_eq!(parse_bitmap("01002000000000000000930000", false).unwrap(), vec![3]);
+        assert_eq!(parse_bitmap("0012000000000001002000000000000000930000", true).unwrap(), vec![3]);
+        // Field 8 is not in the catalog, and the fields need not be complete
+        assert_eq!(parse_bitmap("0100810000000000000000000000000000010000", false).unwrap(), vec![8, 128]);
+        assert_eq!(parse_bitmap("0100C000000000000000", false).unwrap_err(), Iso8583Error::TruncatedField { bit: 0, needed: 16, available: 0 });
+        assert!(parse_bitmap("0100", false).is_err());
+    }
+
+    #[test]
+    fn test_hex_case() {
+        assert_eq!("0a1B2c".to_string().canonical_hex(), "0A1B2C");
+
+        let message = "010000000000000010000123abcdEF456789";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(52).unwrap().value, "0123ABCDEF456789");
+
+        let lower = Mode { hex_case: HexCase::Lower, ..Default::default() };
+        let result = parse_iso8583(message, false, &lower, None).unwrap();
+        assert_eq!(result.field(52).unwrap().value, "0123abcdef456789");
+        assert_eq!(result.field(52).unwrap().details, vec!["\tBytes: 01 23 ab cd ef 45 67 89"]);
+
+        // The hex of the details follows the same case
+        let result = parse_iso8583("0100000000000000020000119f2701809505800000000a", false, &lower, None).unwrap();
+        let details = &result.field(55).unwrap().details;
+        assert_eq!(details[0], "  9f 27 |   1 | Cryptogram Information Data | 80 ");
+        assert_eq!(details[1], "  95    |   5 | Terminal Verification Results | 800000000a ");
+        let raw = Mode { tlv_error_policy: TlvErrorPolicy::Raw, ..lower.clone() };
+        let result = parse_iso8583("0100000000000000020000029F1A", false, &raw, None).unwrap();
+        assert_eq!(result.field(55).unwrap().details[1], "\tRaw: 9f1a");
+
+        let spec = FieldSpec::new("PIN", LengthType::Fixed, 8, Encoding::Binary);
+        let field = "0123abcdEF456789".to_string().process_field(52, 16, &spec, &Mode::default()).unwrap();
+        assert_eq!(field.value, "0123ABCDEF456789");
+        let spec = FieldSpec::new("Terminal", LengthType::Fixed, 2, Encoding::Ascii);
+        let field = "3a3B".to_string().process_field(41, 4, &spec, &lower).unwrap();
+        assert_eq!(field.value, ":;");
+        assert_eq!(field.raw.as_deref(), Some("3a3b"));
+    }
+
+    #[test]
+    fn test_card_sequence_number() {
+        let result = parse_iso8583("010000000200000000000001", false, &Mode::default(), None).unwrap();
+        let field = result.field(23).unwrap();
+        assert_eq!(field.name, "Card Sequence Number");
+        assert_eq!(field.value, "001");
+        assert_eq!(field.details, vec!["\tCard Sequence Number: 1"]);
+
+        let result = parse_iso8583("0100000002000000000000A1", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.warnings, vec!["Field 23: Card sequence number 0A1 is not numeric"]);
+
+        let catalog = FieldCatalog::iso1987();
+        for (bit, name) in [
+            (24, "Network International ID"),
+            (25, "POS Condition Code"),
+            (55, "ICC/EMV Data"),
+            (60, "Reserved (National)"),
+            (70, "Network Management Info Code"),
+            (116, "Reserved (National)"),
+        ] {
+            assert_eq!(catalog.get(bit).unwrap().name, name);
+        }
+        assert!(FieldCatalog::iso1993().iter().all(|(_, spec)| !spec.name.is_empty()));
+    }
+
+    #[test]
+    fn test_original_data_elements() {
+        let original = parse_original_data_elements("020000004310141530120000012345600000654321").unwrap();
+        assert_eq!(original.mti, "0200");
+        assert_eq!(original.stan, "000043");
+        assert_eq!(original.transmission_date_time, "1014153012");
+        assert_eq!(original.acquirer_id, "00000123456");
+        assert_eq!(original.forwarding_id, "00000654321");
+        assert!(parse_original_data_elements("0200000043").is_err());
+        assert!(parse_original_data_elements("02000000431014153012000001234560000065432A").is_err());
+
+        let message = "040080000000000000000000004000000000020000004310141530120000012345600000654321";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(
+            result.field(90).unwrap().details,
+            vec!["\tOriginal MTI: 0200 | STAN: 000043 | Date and Time: 1014153012 | Acquirer ID: 00000123456 | Forwarding ID: 00000654321"]
+        );
+    }
+
+    #[test]
+    fn test_account_identification() {
+        let message = "0200A0000000000000000000000014000000400000063132333435361634313131313131313131313131313131";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.bitmap, vec![3, 100, 102]);
+        assert_eq!(result.field(100).unwrap().name, "Receiving Institution ID");
+        assert_eq!(result.field(100).unwrap().value, "123456");
+        assert_eq!(result.field(102).unwrap().value, "4111111111111111");
+        assert_eq!(result.field(102).unwrap().details, vec!["\t(Luhn: OK)"]);
+        assert!(result.warnings.is_empty());
+        assert!(result.field(100).unwrap().details.is_empty());
+
+        let message = "0200A0000000000000000000000014000000400000063132333435361634313131313131313131313131313132";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.warnings, vec!["Field 102: Account number fails the Luhn check"]);
+        assert!(implemented_fields().contains(&103));
+    }
+
+    #[test]
+    fn test_example_messages() {
+        assert!(EXAMPLE_MESSAGES.len() >= 5);
+        for example in EXAMPLE_MESSAGES {
+            let result = parse_iso8583(example.message, example.including_header_length, &example.mode(), None)
+                .unwrap_or_else(|e| panic!("{}: {}", example.name, e));
+            assert_eq!(result.unparsed, "", "{}", example.name);
+            assert!(result.warnings.is_empty(), "{}: {:?}", example.name, result.warnings);
+            if example.private_tlv || example.private_ltv {
+                assert!(result.iter().any(|field| field.sub_elements.is_some()), "{}", example.name);
+            }
+        }
+        assert!(EXAMPLE_MESSAGES.iter().any(|example| parse_bitmap(example.message, false).unwrap().iter().any(|&bit| bit > 64)));
+    }
+
+    #[test]
+    fn test_input_format() {
+        let expected = hex::decode("01002000000000000000930000").unwrap();
+        assert_eq!(InputFormat::Hex.decode(b"0x01 00 2000000000000000930000").unwrap(), expected);
+        assert_eq!(InputFormat::Base64.decode(b"AQAgAAAAAAAAAJMAAA==").unwrap(), expected);
+        assert_eq!(InputFormat::Base64.decode(b"AQAgAAAA\nAAAAAJMAAA").unwrap(), expected);
+        assert_eq!(InputFormat::Raw.decode(&expected).unwrap(), expected);
+        let result = parse_iso8583_bytes(&InputFormat::Base64.decode(b"AQAgAAAAAAAAAJMAAA==").unwrap(), false, &Mode::default(), None).unwrap();
+        assert_eq!(result.field(3).unwrap().value, "930000");
+
+        assert_eq!(
+            InputFormat::Base64.decode(b"AQAgAAAA!AAAkwAA").unwrap_err(),
+            Iso8583Error::InvalidBase64("invalid character '!' at index 8".to_string())
+        );
+        assert!(InputFormat::Base64.decode(b"AQAgA").is_err());
+        assert!(InputFormat::Base64.decode(b"AQAgAAAAAAAAkwAA=").is_err());
+        assert!(InputFormat::Base64.decode(b"AQ==AA").is_err());
+    }
+
+    #[test]
+    fn test_bitmap_format() {
+        let result = parse_iso8583("01002020000000000000930000000001", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.bitmap, vec![3, 11]);
+        assert_eq!(result.bitmap_hex, "2020000000000000");
+        let format = |bitmap_format| result.format(&FormatOptions { bitmap_format, ..Default::default() });
+        assert!(format(BitmapFormat::Positions).contains("First Bit Map: [3, 11]\n"));
+        assert!(format(BitmapFormat::Hex).contains("First Bit Map: 2020000000000000\n"));
+        assert!(format(BitmapFormat::Both).contains("First Bit Map: 2020000000000000 [3, 11]\n"));
+
+        let message = "040080000000000000000000004000000000020000004310141530120000012345600000654321";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        assert_eq!(result.bitmap_hex, "80000000000000000000004000000000");
+    }
+
+    #[test]
+    fn test_additional_response_data_1993() {
+        // Longer than the 25 characters of 1987
+        let result = parse_iso8583("11100000000000100000324144444954494F4E414C20524553504F4E53452044415441204F462031393933", false, &Mode::default(), None).unwrap();
+        let field = result.field(44).unwrap();
+        assert_eq!(field.name, "Additional response data");
+        assert_eq!(field.value, "ADDITIONAL RESPONSE DATA OF 1993");
+        assert_eq!(result.unparsed, "");
+    }
+
+    #[test]
+    fn test_response_category() {
+        assert_eq!(response_category("00"), ResponseCategory::Approved);
+        assert_eq!(response_category("10"), ResponseCategory::Approved);
+        assert_eq!(response_category("002"), ResponseCategory::Approved);
+        assert_eq!(response_category("01"), ResponseCategory::Referral);
+        assert_eq!(response_category("107"), ResponseCategory::Referral);
+        assert_eq!(response_category("05"), ResponseCategory::Declined);
+        assert_eq!(response_category("116"), ResponseCategory::Declined);
+        assert_eq!(response_category("ZZ"), ResponseCategory::Declined);
+
+        // A 1993 approval, with the 3-character action code of field 39
+        let result = parse_iso8583("11100000000002000000303030", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.response_category(), Some(ResponseCategory::Approved));
+        assert!(result.to_string().contains("Response Category: approved\n"));
+        let result = parse_iso8583("11100000000002000000313037", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.response_category(), Some(ResponseCategory::Referral));
+        let result = parse_iso8583("01002000000000000000930000", false, &Mode::default(), None).unwrap();
+        assert_eq!(result.response_category(), None);
+    }
+
+    #[test]
+    fn test_card_acceptor_location() {
+        let message = "010000000000002000005355504552204D41524B45542032342020202020202020202053484952415A202020202020204952";
+        let result = parse_iso8583(message, false, &Mode::default(), None).unwrap();
+        let field = result.field(43).unwrap();
+        assert_eq!(field.value, "SUPER MARKET 24          SHIRAZ       IR");
+        assert_eq!(field.details, vec!["\tName: SUPER MARKET 24 | City: SHIRAZ | State/Country: IR"]);
+        assert_eq!(result.unparsed, "");
+    }
+
     // Add more tests for other functions and methods
 }
\ No newline at end of file
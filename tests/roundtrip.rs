@@ -0,0 +1,93 @@
+//! Round trip tests: messages built by `Iso8583Builder` from random field sets must parse
+//! back to the same fields.
+
+use iso8583_parser::{parse_iso8583, Encoding, FieldCatalog, FieldSpec, Iso8583Builder, LengthType, Mode};
+
+/// Xorshift generator, so failures can be reproduced from their seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Fields whose values are not reported as they were given.
+fn skipped(bit: u32) -> bool {
+    // The EMV decoder of field 55 expects valid TLV
+    bit == 55
+}
+
+fn random_value(rng: &mut Rng, spec: &FieldSpec) -> String {
+    let length = match spec.length_type {
+        LengthType::Fixed => spec.max_length,
+        LengthType::LLVar | LengthType::LLLVar => rng.below(spec.max_length.min(40) as u64 + 1) as u32,
+    };
+    (0..length)
+        .map(|_| match spec.encoding {
+            Encoding::Bcd => char::from(b'0' + rng.below(10) as u8).to_string(),
+            Encoding::Ascii | Encoding::Ebcdic => char::from(b'A' + rng.below(26) as u8).to_string(),
+            Encoding::Binary => format!("{:02X}", rng.below(256)),
+        })
+        .collect()
+}
+
+/// Build a message from a random set of the fields accepted by `bits`, parse it back and
+/// compare.
+fn check_round_trip(seed: u64, mti: &str, bits: impl Fn(u32) -> bool) {
+    let mut rng = Rng(seed);
+    let catalog = FieldCatalog::for_mti(mti);
+    let mut builder = Iso8583Builder::new();
+    builder.set_mti(mti);
+    let mut expected = Vec::new();
+    for (bit, spec) in catalog.iter().filter(|&(bit, _)| bits(bit) && !skipped(bit)) {
+        if rng.below(3) == 0 {
+            let value = random_value(&mut rng, spec);
+            builder.set_field(bit, &value);
+            expected.push((bit, value));
+        }
+    }
+
+    let message = builder.build().unwrap();
+    assert!(message.len().is_multiple_of(2), "seed {}: {} is not made of whole bytes", seed, message);
+    let result = parse_iso8583(&message, false, &Mode::default(), None)
+        .unwrap_or_else(|e| panic!("seed {}: {} does not parse: {}", seed, message, e));
+    let parsed: Vec<(u32, String)> = result.iter().map(|field| (field.number, field.value.clone())).collect();
+    assert_eq!(parsed, expected, "seed {}: {}", seed, message);
+    assert_eq!(result.unparsed, "", "seed {}: {}", seed, message);
+}
+
+#[test]
+fn primary_bitmap_round_trip() {
+    for seed in 1..=300 {
+        check_round_trip(seed, "0200", |bit| bit <= 64);
+    }
+}
+
+#[test]
+fn secondary_bitmap_round_trip() {
+    for seed in 1..=300 {
+        check_round_trip(seed, "0200", |bit| bit <= 128);
+    }
+}
+
+#[test]
+fn iso1993_round_trip() {
+    for seed in 1..=300 {
+        check_round_trip(seed, "1200", |bit| bit <= 128);
+    }
+}
+
+#[test]
+fn tertiary_bitmap_round_trip() {
+    for seed in 1..=100 {
+        check_round_trip(seed, "0200", |_| true);
+    }
+}
@@ -0,0 +1,155 @@
+//! Test vectors: complete messages of the kinds seen in production, with the values the
+//! parser is expected to report for them.
+//!
+//! The messages were built with `Iso8583Builder` following the layout of typical acquirer
+//! messages. They use the public test cards of the schemes and placeholder terminal and
+//! merchant IDs, so they carry no cardholder data.
+
+use iso8583_parser::{parse_iso8583, Mode, ResponseCategory, SubElements, LTV};
+
+fn parse(message: &str) -> iso8583_parser::ParserResult {
+    parse_iso8583(message, false, &Mode::default(), None).unwrap_or_else(|e| panic!("{} does not parse: {}", message, e))
+}
+
+fn value(result: &iso8583_parser::ParserResult, bit: u32) -> &str {
+    &result.field(bit).unwrap_or_else(|| panic!("field {} is missing", bit)).value
+}
+
+/// Chip and PIN purchase authorization of 100.00 USD from a POS terminal, with the card
+/// sequence number and the track 2 equivalent data read from the chip. The card is the Visa
+/// test card of the EMVCo test kits.
+const AUTHORIZATION_REQUEST: &str = "01007224468028C080001647617390010101190000000000000100001014153012123456271254110051000100344761739001010119D271220111438044893432383731353132333435365445524D303030314D45524348414E5430303030303031383430";
+
+/// The approval of `AUTHORIZATION_REQUEST`, echoing its trace and retrieval reference number
+/// with an authorization code and the ASCII response code 00.
+const AUTHORIZATION_RESPONSE: &str = "0110722000000EC08000164761739001010119000000000000010000101415301212345634323837313531323334353641314232433330305445524D303030314D45524348414E5430303030303031383430";
+
+/// Financial purchase of 25.50 EUR with the Mastercard M-TIP test card, carrying the local
+/// date and time and the card acceptor name and location.
+const FINANCIAL_REQUEST: &str = "02007038040008E08000165413330089020011000000000000002550000042153012101400513030303030303030303034325445524D303030314D45524348414E543030303030303141434D452053544F524520202020202020202020202020202054454852414E202020202020204952393738";
+
+/// Echo test sent by a host to keep a connection alive, identified by the network management
+/// information code of field 70 in the secondary bitmap.
+const ECHO_TEST: &str = "08008220000000000000040000000000000010141530120000010301";
+
+/// Refund referring to an earlier financial message through the original data elements of
+/// field 90, and closed by a MAC in field 128 of the secondary bitmap.
+const SECONDARY_BITMAP: &str = "0200B02000000080000000000040000000012000000000000050000000435445524D303030310200000043101415301200000000000000000000000102030405060708";
+
+/// Chip authorization carrying the EMV data of the first GENERATE AC in field 55: the
+/// cryptogram, its information data, the ATC, the TVR, the AIP and the transaction type.
+const EMV_REQUEST: &str = "0100302000000000020000000000000001000000004400349F2608D547079427A420B79F2701809F360200149505800000000082025C009C0100";
+
+/// ISO 8583:1993 authorization response approving 100.00 with the action code 000 in field 39.
+const ISO1993_RESPONSE: &str = "121030200000028000000000000000000100000000443030305445524D30303031";
+
+/// Authorization carrying private LTV elements in field 48, in the layout read by `-l`: tag 1
+/// holds the byte AB and tag 2 the bytes 41 42.
+const PRIVATE_LTV: &str = "0100202000000001000000000000004500070201AB03024142";
+
+#[test]
+fn authorization_request() {
+    let result = parse(AUTHORIZATION_REQUEST);
+    assert_eq!(result.mti, "0100");
+    assert_eq!(result.bitmap, vec![2, 3, 4, 7, 11, 14, 18, 22, 23, 25, 35, 37, 41, 42, 49]);
+    assert_eq!(value(&result, 2), "4761739001010119");
+    assert_eq!(value(&result, 4), "000000010000");
+    assert_eq!(value(&result, 23), "001");
+    assert_eq!(value(&result, 35), "4761739001010119D27122011143804489");
+    assert_eq!(value(&result, 41), "TERM0001");
+    assert_eq!(value(&result, 42), "MERCHANT0000001");
+    assert_eq!(value(&result, 49), "840");
+    assert_eq!(result.unparsed, "");
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn authorization_response() {
+    let result = parse(AUTHORIZATION_RESPONSE);
+    let request = parse(AUTHORIZATION_REQUEST);
+    assert_eq!(result.mti, "0110");
+    assert_eq!(result.bitmap, vec![2, 3, 4, 7, 11, 37, 38, 39, 41, 42, 49]);
+    for bit in [2, 3, 4, 7, 11, 37, 41, 42, 49] {
+        assert_eq!(value(&result, bit), value(&request, bit), "field {}", bit);
+    }
+    assert_eq!(value(&result, 38), "A1B2C3");
+    assert_eq!(result.field(39).unwrap().details, vec!["\tResponse Code 00: Approved"]);
+    assert_eq!(result.unparsed, "");
+}
+
+#[test]
+fn financial_request() {
+    let result = parse(FINANCIAL_REQUEST);
+    assert_eq!(result.mti, "0200");
+    assert_eq!(result.bitmap, vec![2, 3, 4, 11, 12, 13, 22, 37, 41, 42, 43, 49]);
+    assert_eq!(value(&result, 2), "5413330089020011");
+    assert_eq!(value(&result, 4), "000000002550");
+    assert_eq!(value(&result, 12), "153012");
+    assert_eq!(value(&result, 13), "1014");
+    assert_eq!(value(&result, 49), "978");
+    assert_eq!(value(&result, 43), "ACME STORE               TEHRAN       IR");
+    assert_eq!(result.field(43).unwrap().details, vec!["\tName: ACME STORE | City: TEHRAN | State/Country: IR"]);
+    assert_eq!(result.unparsed, "");
+}
+
+#[test]
+fn echo_test() {
+    let result = parse(ECHO_TEST);
+    assert_eq!(result.mti, "0800");
+    assert_eq!(result.bitmap, vec![7, 11, 70]);
+    assert_eq!(value(&result, 7), "1014153012");
+    assert_eq!(value(&result, 70), "0301");
+    assert_eq!(result.network_management(), Some("Echo Test"));
+    assert_eq!(result.unparsed, "");
+}
+
+#[test]
+fn secondary_bitmap() {
+    let result = parse(SECONDARY_BITMAP);
+    assert_eq!(result.bitmap, vec![3, 4, 11, 41, 90, 128]);
+    assert_eq!(value(&result, 3), "200000");
+    assert_eq!(value(&result, 90), "020000004310141530120000000000000000000000");
+    assert_eq!(result.mac, Some(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+    assert_eq!(result.unparsed, "");
+}
+
+#[test]
+fn emv_request() {
+    let result = parse(EMV_REQUEST);
+    assert_eq!(result.bitmap, vec![3, 4, 11, 55]);
+    let tags: Vec<&str> = result.emv_tags.iter().map(|tag| tag.tag.as_str()).collect();
+    assert_eq!(tags, vec!["9F26", "9F27", "9F36", "95", "82", "9C"]);
+    assert_eq!(result.emv_tags[0].value, vec![0xD5, 0x47, 0x07, 0x94, 0x27, 0xA4, 0x20, 0xB7]);
+    let details = &result.field(55).unwrap().details;
+    assert_eq!(details[3], "  95    |   5 | Terminal Verification Results | 8000000000 ");
+    assert!(details.contains(&"\tTVR: Offline data authentication was not performed".to_string()));
+    assert_eq!(result.field(55).unwrap().sub_elements, None);
+    assert_eq!(result.unparsed, "");
+}
+
+#[test]
+fn iso1993_response() {
+    let result = parse(ISO1993_RESPONSE);
+    assert_eq!(result.mti, "1210");
+    assert_eq!(result.bitmap, vec![3, 4, 11, 39, 41]);
+    assert_eq!(value(&result, 39), "000");
+    assert_eq!(result.field(39).unwrap().name, "Action Code");
+    assert_eq!(result.response_category(), Some(ResponseCategory::Approved));
+    assert_eq!(value(&result, 41), "TERM0001");
+    assert_eq!(result.unparsed, "");
+}
+
+#[test]
+fn private_ltv() {
+    let result = parse(PRIVATE_LTV);
+    assert_eq!(result.bitmap, vec![3, 11, 48]);
+    assert_eq!(value(&result, 48), "0201AB03024142");
+
+    let ltv = Mode { enabled_private_ltv: true, ..Default::default() };
+    let result = parse_iso8583(PRIVATE_LTV, false, &ltv, None).unwrap();
+    let expected = vec![
+        LTV { length: 2, tag: 1, value: "AB".to_string() },
+        LTV { length: 3, tag: 2, value: "4142".to_string() },
+    ];
+    assert_eq!(result.field(48).unwrap().sub_elements, Some(SubElements::Ltv(expected)));
+}